@@ -1,33 +1,18 @@
-use std::collections::hash_map::*;
+use std::convert::TryFrom;
+use std::env;
 use std::ffi::OsStr;
 use std::fs::File;
-use std::env;
-use std::io::{self, BufReader};
+use std::io::{self, BufWriter};
 use std::path::{Path, PathBuf};
-use std::str;
-use hangouts_json_parser::{raw, Hangouts};
+use hangouts_json_parser::{Error, Hangouts};
+use hangouts_json_parser::export::html::Exporter;
+use hangouts_json_parser::model::{Archive, Conversation};
+use hangouts_json_parser::render::file_url;
+use hangouts_json_parser::resolver::{AttachmentResolver, Directory};
 
 fn usage() {
-    eprintln!("usage: {} <json path> <participant name>", env::args().next().unwrap());
-}
-
-fn chrono(parts: Result<(i64, u32), std::num::ParseIntError>) -> chrono::DateTime<chrono::Utc> {
-    let (secs, nsecs) = parts.expect("bad timestamp");
-    chrono::TimeZone::timestamp(&chrono::Utc, secs, nsecs)
-}
-
-fn start_formatting(formatting: &raw::Formatting) {
-    if formatting.bold { print!("<b>"); }
-    if formatting.italics { print!("<i>"); }
-    if formatting.strikethrough { print!("<s>"); }
-    if formatting.underline { print!("<ul>"); }
-}
-
-fn end_formatting(formatting: &raw::Formatting) {
-    if formatting.bold { print!("</b>"); }
-    if formatting.italics { print!("</i>"); }
-    if formatting.strikethrough { print!("</s>"); }
-    if formatting.underline { print!("</ul>"); }
+    eprintln!("usage: {} <json path> <participant name or conversation ID> [<output directory>]",
+        env::args().next().unwrap());
 }
 
 fn parent_path(s: &OsStr) -> Result<PathBuf, io::Error> {
@@ -36,112 +21,15 @@ fn parent_path(s: &OsStr) -> Result<PathBuf, io::Error> {
     Ok(canonical)
 }
 
-fn urldecode(s: &str) -> Result<String, String> {
-    let mut bytes = vec![];
-    let mut skip = 0;
-    for (i, byte) in s.as_bytes().iter().cloned().enumerate() {
-        if skip > 0 {
-            skip -= 1;
-            continue;
-        }
-
-        if byte == b'%' {
-            let num_str = s.get(i + 1 .. i + 3)
-                .ok_or("%-encoded character cut short")?;
-            let n = u8::from_str_radix(num_str, 16)
-                .map_err(|e| format!("invalid %-encoded character: {}", e))?;
-            bytes.push(n);
-            skip = 2;
-        } else {
-            bytes.push(byte);
-        }
-    }
-
-    String::from_utf8(bytes)
-        .map_err(|e| format!("{}", e))
+fn matches(convo: &Conversation, query: &str) -> bool {
+    let id = convo.id == query;
+    let query = query.to_lowercase();
+    id || convo.name.as_ref().is_some_and(|name| name.to_lowercase() == query)
+        || convo.participants.iter()
+            .any(|p| p.name.as_ref().is_some_and(|name| name.to_lowercase() == query))
 }
 
-fn find_local_file(url: &str, base_path: &Path) -> Option<PathBuf> {
-    let url_filename = url
-        .rsplit_terminator('/')
-        .next().unwrap();
-    let decoded_filename = urldecode(url_filename)
-        .map_err(|e| {
-            eprintln!("bad URL {:?}: {}", url_filename, e);
-            e
-        })
-        .ok()?;
-
-    let mut filename = decoded_filename.clone();
-    let mut localpath = base_path.join(&filename);
-
-    loop {
-        if localpath.exists() {
-            return Some(localpath);
-        }
-
-        if localpath.extension().is_none() {
-            localpath.set_extension("jpg");
-            if localpath.exists() {
-                return Some(localpath);
-            }
-        }
-
-        // Try unwrapping another layer of urlencoding.
-        if let Ok(decoded) = urldecode(&filename) {
-            if decoded == filename {
-                break;
-            }
-            filename = decoded;
-            localpath.set_file_name(&filename);
-        } else {
-            break;
-        }
-    }
-
-    // Try again, additionally replacing some characters because this is what Google does sometimes.
-    filename = decoded_filename;
-    loop {
-        filename = filename.replace('+', " ");
-        filename = filename.replace('?', "_");
-        localpath.set_file_name(&filename);
-
-        if localpath.exists() {
-            return Some(localpath);
-        }
-
-        if localpath.extension().is_none() {
-            localpath.set_extension("jpg");
-            if localpath.exists() {
-                return Some(localpath);
-            }
-        }
-
-        // Try unwrapping another layer of urlencoding.
-        if let Ok(decoded) = urldecode(&filename) {
-            if decoded == filename {
-                break;
-            }
-            filename = decoded;
-        } else {
-            break;
-        }
-    }
-
-    None
-}
-
-fn file_url(path: &Path) -> String {
-    let s = path.to_str().expect("non-utf8 path");
-    if cfg!(windows) && s.starts_with(r"\\?\") {
-        // Browsers don't like "\\?\" paths; remove the prefix.
-        format!("file:///{}", &s[4..])
-    } else {
-        format!("file:///{}", s)
-    }
-}
-
-fn main() -> Result<(), io::Error> {
+fn main() -> Result<(), Error> {
     let path = env::args_os()
         .nth(1)
         .unwrap_or_else(|| {
@@ -154,100 +42,50 @@ fn main() -> Result<(), io::Error> {
             std::process::exit(2);
         });
 
-    let participant_name = env::args()
+    let query = env::args()
         .nth(2)
         .unwrap_or_else(|| {
             usage();
             std::process::exit(2);
         });
 
-    let mut hangouts: Hangouts = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let output_dir = env::args_os().nth(3).map(PathBuf::from);
 
-    let convo = hangouts.conversations
-        .iter_mut()
-        .find(|convo|
-            convo.header.details.participant_data.iter().any(|p|
-                p.fallback_name.as_ref() == Some(&participant_name)))
-        .unwrap_or_else(|| {
-            eprintln!("No matching conversation found with a person named {:?}", participant_name);
-            std::process::exit(1);
-        });
+    let resolver = AttachmentResolver::new(Directory::open(&base_path)?);
+    let exporter = Exporter::new().attachment_urls(|attachment| {
+        resolver.resolve(attachment).and_then(|name| file_url(&resolver.source().path(&name)))
+    });
 
-    let names: HashMap<raw::ParticipantId, String> = convo.header.details.participant_data
+    let hangouts: Hangouts = Hangouts::from_path(path)?;
+    let archive = Archive::try_from(hangouts)?;
+    let found = archive.conversations
         .iter()
-        .map(|p| (p.id.clone(), p.fallback_name.as_deref().unwrap_or("[unknown]").to_owned()))
-        .collect();
-
-    println!("<!DOCTYPE html>");
-    println!("<html>");
-    println!("<head><meta charset=\"utf-8\"/></head>");
-    println!("<body>");
-
-    convo.events.sort_unstable_by_key(|event| event.header.timestamp.clone());
-    for event in &convo.events {
-        let dt = chrono(event.header.timestamp()).format("%Y-%m-%d %H:%M:%S");
-        let name = &names[&event.header.sender_id];
-        println!("[{}] {}: ", dt, name);
-        match event.data {
-            raw::EventData::ChatMessage { ref message_content, .. } => {
-                for segment in &message_content.segments {
-                    match segment {
-                        raw::ChatSegment::Text { ref text, formatting, } => {
-                            start_formatting(&formatting);
-                            println!("{}", text);
-                            end_formatting(&formatting);
-                        }
-                        raw::ChatSegment::Link { text, link_data, formatting } => {
-                            start_formatting(&formatting);
-                            println!("<a href=\"{}\">{}</a>",
-                                link_data.link_target, text);
-                            end_formatting(&formatting);
-                        }
-                        raw::ChatSegment::LineBreak { text: _ } => {
-                            println!("<br />");
-                        }
-                    }
-                }
+        .filter(|convo| matches(convo, &query))
+        .collect::<Vec<_>>();
 
-                for attachment in &message_content.attachments {
-                    if let Some(ref photo) = attachment.embed_item.plus_photo {
-                        if let Some(path) = find_local_file(
-                            &photo.url, &base_path)
-                        {
-                            println!("<img src=\"{}\"", file_url(&path));
-                        } else {
-                            println!("<img src=\"{}\"", photo.url);
-                        }
-                        // TODO: maybe make a thumbnail: smaller image and link to full one?
-                        println!("width=\"100%\"/>");
-                    } else {
-                        println!("[an attachment: <pre>{:#?}</pre>]", attachment);
-                    }
-                }
-            }
-
-            raw::EventData::HangoutEvent { ref data, .. } => {
-                match data {
-                    raw::HangoutEvent::StartHangout => {
-                        println!("<i>[call started]</i><br />");
-                    }
-                    raw::HangoutEvent::EndHangout { ref hangout_duration_secs } => {
-                        println!("<i>[call ended; duration was {} seconds]</i><br />",
-                            hangout_duration_secs);
-                    }
-                }
-            }
-
-            raw::EventData::MembershipChange { .. } => {
-                println!("<i>[membership change: {:#?}]</i>", event.data);
+    match (&output_dir, found.as_slice()) {
+        (_, []) => {
+            eprintln!("No conversation found with ID, name, or participant {:?}", query);
+            std::process::exit(1);
+        }
+        (None, [convo]) => exporter.write_conversation(convo, io::stdout().lock())?,
+        (None, _) => {
+            eprintln!("{} conversations match; pick one by ID, or give an output directory:",
+                found.len());
+            for convo in found {
+                eprintln!("  {}  {}", convo.id, convo.display_name());
             }
-
-            raw::EventData::ConversationRename { ref old_name, ref new_name } => {
-                println!("<i>[conversation renamed from {:?} to {:?}]</i>", old_name, new_name);
+            std::process::exit(1);
+        }
+        (Some(dir), _) => {
+            for convo in found {
+                // IDs are URL-safe base64-ish, but don't trust them to be file names.
+                let name = convo.id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+                let path = dir.join(format!("{}.html", name));
+                exporter.write_conversation(convo, BufWriter::new(File::create(&path)?))?;
+                println!("{}: {}", path.display(), convo.display_name());
             }
         }
-        println!("<hr />");
     }
-    println!("</body></html>");
     Ok(())
 }
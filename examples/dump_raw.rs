@@ -1,18 +1,16 @@
-use std::fs::File;
 use std::env;
-use std::io::{self, BufReader};
-use hangouts_json_parser::Hangouts;
+use hangouts_json_parser::{Error, Hangouts};
 
-fn main() -> Result<(), io::Error> {
-    let input = if let Some(path) = env::args_os().nth(1) {
-        BufReader::new(File::open(path)?)
+fn main() -> Result<(), Error> {
+    let path = if let Some(path) = env::args_os().nth(1) {
+        path
     } else {
         eprintln!("usage: {} <path>", env::args().next().unwrap());
         std::process::exit(1);
     };
 
-    let h: Hangouts = serde_json::from_reader(input)?;
-
-    println!("{:#?}", h);
+    for convo in Hangouts::stream_path(path)? {
+        println!("{:#?}", convo?);
+    }
     Ok(())
 }
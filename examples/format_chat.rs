@@ -1,19 +1,14 @@
-use std::collections::hash_map::*;
-use std::fs::File;
+use std::convert::TryFrom;
 use std::env;
-use std::io::{self, BufReader};
-use hangouts_json_parser::{raw, Hangouts};
+use hangouts_json_parser::{Error, Hangouts};
+use hangouts_json_parser::model::{Archive, CallKind, EventKind, SystemEvent};
+use hangouts_json_parser::render::{PlainText, Renderer};
 
 fn usage() {
     eprintln!("usage: {} <json path> <participant name>", env::args().next().unwrap());
 }
 
-fn chrono(parts: Result<(i64, u32), std::num::ParseIntError>) -> chrono::DateTime<chrono::Utc> {
-    let (secs, nsecs) = parts.expect("bad timestamp");
-    chrono::TimeZone::timestamp(&chrono::Utc, secs, nsecs)
-}
-
-fn main() -> Result<(), io::Error> {
+fn main() -> Result<(), Error> {
     let path = env::args_os().nth(1).unwrap_or_else(|| {
         usage();
         std::process::exit(2);
@@ -24,77 +19,53 @@ fn main() -> Result<(), io::Error> {
         std::process::exit(2);
     });
 
-    let mut hangouts: Hangouts = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let hangouts: Hangouts = Hangouts::from_path(path)?;
+    let archive = Archive::try_from(hangouts)?;
 
-    let convo = hangouts.conversations
-        .iter_mut()
+    let convo = archive.conversations
+        .iter()
         .find(|convo|
-            convo.header.details.participant_data.iter().any(|p|
-                p.fallback_name.as_ref() == Some(&participant_name)))
+            convo.participants.iter().any(|p|
+                p.name.as_ref() == Some(&participant_name)))
         .unwrap_or_else(|| {
             eprintln!("No matching conversation found with a person named {:?}", participant_name);
             std::process::exit(1);
         });
 
-    let names: HashMap<raw::ParticipantId, String> = convo.header.details.participant_data
-        .iter()
-        .map(|p| (p.id.clone(), p.fallback_name.as_deref().unwrap_or("[unknown]").to_owned()))
-        .collect();
-
-    convo.events.sort_unstable_by_key(|event| event.header.timestamp.clone());
     for event in &convo.events {
-        let dt = chrono(event.header.timestamp()).format("%Y-%m-%d %H:%M:%S");
-        let name = &names[&event.header.sender_id];
-        let text = match event.data {
-            raw::EventData::ChatMessage { ref message_content, .. } => {
-                let mut combined = String::new();
-
-                for segment in &message_content.segments {
-                    match segment {
-                        raw::ChatSegment::Text { ref text, formatting: _ } => {
-                            //combined += &format!("[text: {}]", text);
-                            combined += text;
-                        }
-                        raw::ChatSegment::Link {
-                            text: _,
-                            ref link_data,
-                            formatting: _,
-                        } => {
-                            combined += &format!("[link: {:?}]", link_data);
-                        }
-                        raw::ChatSegment::LineBreak { ref text } => {
-                            if let Some(text) = text {
-                                combined += text;
-                            } else {
-                                combined.push('\n');
-                            }
-                        }
-                    }
-                }
-
-                for attachment in &message_content.attachments {
-                    combined += &format!("[an attachment: {:#?}]", attachment);
-                }
-
-                combined
+        let dt = chrono::DateTime::<chrono::Utc>::from(event.timestamp.to_system_time()).format("%Y-%m-%d %H:%M:%S");
+        let name = event.sender.display_name();
+        let text = match event.kind {
+            EventKind::Message(ref msg) => {
+                println!("[{}] {}", dt, PlainText.render_message(name, &msg.body()));
+                continue;
             }
 
-            raw::EventData::HangoutEvent { ref data, .. } => {
-                match data {
-                    raw::HangoutEvent::StartHangout => "[call started]".to_owned(),
-                    raw::HangoutEvent::EndHangout { ref hangout_duration_secs } => {
-                        format!("[call ended; duration was {} seconds", hangout_duration_secs)
+            EventKind::Call(ref call) => {
+                match call.kind {
+                    CallKind::Started => "[call started]".to_owned(),
+                    CallKind::Ended { duration } => {
+                        format!("[call ended; duration was {} seconds]", duration.as_secs())
+                    }
+                    CallKind::Missed => "[missed call]".to_owned(),
+                    CallKind::Voicemail { duration, ref transcript } => {
+                        format!("[voicemail, {} seconds: {}]", duration.as_secs(),
+                            transcript.as_deref().unwrap_or("no transcript"))
                     }
+                    CallKind::Unknown(ref value) => format!("[unknown call event: {}]", value),
                 }
             }
 
-            raw::EventData::MembershipChange { .. } => {
-                format!("[membership change: {:#?}]", event.data)
+            EventKind::System(SystemEvent::MembershipChange { ref kind, ref participants }) => {
+                let names = participants.iter().map(|p| p.display_name()).collect::<Vec<_>>();
+                format!("[membership change: {:?} {}]", kind, names.join(", "))
             }
 
-            raw::EventData::ConversationRename { ref old_name, ref new_name } => {
+            EventKind::System(SystemEvent::Rename { ref old_name, ref new_name }) => {
                 format!("[conversation rename from {:?} to {:?}]", old_name, new_name)
             }
+
+            EventKind::Unknown(ref value) => format!("[unknown event: {}]", value),
         };
 
         println!("[{}] {}: {}", dt, name, text);
@@ -0,0 +1,39 @@
+use std::convert::TryFrom;
+use std::env;
+use std::path::{Path, PathBuf};
+use hangouts_json_parser::{Error, Hangouts};
+use hangouts_json_parser::export::html::Exporter;
+use hangouts_json_parser::export::site::Site;
+use hangouts_json_parser::model::Archive;
+use hangouts_json_parser::render::file_url;
+use hangouts_json_parser::resolver::{AttachmentResolver, Directory};
+
+fn usage() {
+    eprintln!("usage: {} <json path> <output directory>", env::args().next().unwrap());
+}
+
+fn main() -> Result<(), Error> {
+    let path = env::args_os().nth(1).unwrap_or_else(|| {
+        usage();
+        std::process::exit(2);
+    });
+
+    let output_dir = env::args_os().nth(2).map(PathBuf::from).unwrap_or_else(|| {
+        usage();
+        std::process::exit(2);
+    });
+
+    // Attachments are linked to where they are in the export, rather than copied.
+    let mut base_path = Path::new(&path).canonicalize()?;
+    base_path.pop();
+    let resolver = AttachmentResolver::new(Directory::open(&base_path)?);
+    let exporter = Exporter::new().attachment_urls(|attachment| {
+        resolver.resolve(attachment).and_then(|name| file_url(&resolver.source().path(&name)))
+    });
+
+    let hangouts: Hangouts = Hangouts::from_path(path)?;
+    let archive = Archive::try_from(hangouts)?;
+    Site::new(exporter).write(&archive, &output_dir)?;
+    println!("{}", output_dir.join("index.html").display());
+    Ok(())
+}
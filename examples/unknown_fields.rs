@@ -0,0 +1,22 @@
+use std::env;
+use hangouts_json_parser::{Error, Hangouts};
+use hangouts_json_parser::lenient::UnknownReport;
+
+fn main() -> Result<(), Error> {
+    let path = if let Some(path) = env::args_os().nth(1) {
+        path
+    } else {
+        eprintln!("usage: {} <path>", env::args().next().unwrap());
+        std::process::exit(1);
+    };
+
+    let mut report = UnknownReport::default();
+    for (i, convo) in Hangouts::stream_path(path)?.enumerate() {
+        report.scan_conversation(i, &convo?);
+    }
+
+    for (path, count) in report.summary() {
+        println!("{}\t{}", count, path);
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! A binary cache of parsed archives, with the "cache" feature.
+//!
+//! Tools that are run repeatedly on the same Hangouts.json can save what they parsed with
+//! `Hangouts::save_cache`, and next time load it with `Hangouts::load_cache`, which is many times
+//! faster than parsing the JSON again. The cache is written next to the source file, with
+//! `.cache` appended to its name, and records the source's size, modification time, and hash. It
+//! is ignored if the source no longer matches, or if it was written by a different version of this
+//! library.
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::index::file_metadata;
+use crate::{Error, Hangouts};
+
+const MAGIC: &[u8; 8] = b"HJPCACHE";
+
+/// Bumped whenever the layout of the file changes.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Header {
+    /// The `raw` types can change between versions, and with the "lenient" feature. The
+    /// "deny_unknown_fields" and "strict" features promise validation that a cache written without
+    /// them would skip.
+    crate_version: String,
+    lenient: bool,
+    deny_unknown_fields: bool,
+    strict: bool,
+    source: Fingerprint,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Fingerprint {
+    len: u64,
+    /// Seconds and nanoseconds since the Unix epoch, if the platform has modification times.
+    modified: Option<(u64, u32)>,
+    hash: u64,
+}
+
+impl Fingerprint {
+    fn of(source: &Path) -> io::Result<Self> {
+        let (len, modified) = file_metadata(source)?;
+        Ok(Fingerprint { len, modified, hash: hash_file(source)? })
+    }
+
+    /// Whether the source is unchanged. It's only hashed if its size is the same but it has been
+    /// touched since.
+    fn matches(&self, source: &Path) -> io::Result<bool> {
+        let (len, modified) = file_metadata(source)?;
+        if len != self.len {
+            return Ok(false);
+        }
+        Ok(modified == self.modified || hash_file(source)? == self.hash)
+    }
+}
+
+fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        match file.read(&mut buf)? {
+            0 => return Ok(hasher.digest()),
+            n => hasher.update(&buf[..n]),
+        }
+    }
+}
+
+impl Header {
+    fn new(source: Fingerprint) -> Self {
+        Header {
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            lenient: cfg!(feature = "lenient"),
+            deny_unknown_fields: cfg!(feature = "deny_unknown_fields"),
+            strict: cfg!(feature = "strict"),
+            source,
+        }
+    }
+
+    fn is_compatible(&self) -> bool {
+        self.crate_version == env!("CARGO_PKG_VERSION")
+            && self.lenient == cfg!(feature = "lenient")
+            && self.deny_unknown_fields == cfg!(feature = "deny_unknown_fields")
+            && self.strict == cfg!(feature = "strict")
+    }
+}
+
+/// Where the cache for the given source file goes: the same path with `.cache` appended.
+pub fn cache_path(source: impl AsRef<Path>) -> PathBuf {
+    let mut path = OsString::from(source.as_ref());
+    path.push(".cache");
+    PathBuf::from(path)
+}
+
+impl Hangouts {
+    /// Write this archive to a cache next to the file it was parsed from.
+    pub fn save_cache(&self, source: impl AsRef<Path>) -> Result<(), Error> {
+        let source = source.as_ref();
+        let header = Header::new(Fingerprint::of(source)?);
+
+        // Written under another name and then renamed, so an interrupted write leaves no cache
+        // rather than a truncated one.
+        let path = cache_path(source);
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        let result = self.write_cache(&header, Path::new(&temp))
+            .and_then(|()| fs::rename(&temp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        Ok(result?)
+    }
+
+    fn write_cache(&self, header: &Header, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        // Structs are written as maps, because fields skipped when empty would otherwise throw
+        // the rest out of position.
+        rmp_serde::encode::write_named(&mut file, header).map_err(io::Error::other)?;
+        rmp_serde::encode::write_named(&mut file, self).map_err(io::Error::other)?;
+        file.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()
+    }
+
+    /// Load the archive cached for the given source file, if there is a cache and it's still
+    /// valid.
+    pub fn load_cache(source: impl AsRef<Path>) -> Result<Option<Hangouts>, Error> {
+        let source = source.as_ref();
+        let mut file = match File::open(cache_path(source)) {
+            Ok(file) => BufReader::new(file),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut preamble = [0; 12];
+        match file.read_exact(&mut preamble) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        if preamble[..8] != MAGIC[..] || preamble[8..] != FORMAT_VERSION.to_le_bytes() {
+            return Ok(None);
+        }
+
+        let header: Header = match rmp_serde::from_read(&mut file) {
+            Ok(header) => header,
+            Err(_) => return Ok(None),
+        };
+        if !header.is_compatible() || !header.source.matches(source)? {
+            return Ok(None);
+        }
+
+        let hangouts = rmp_serde::from_read(&mut file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Some(hangouts))
+    }
+
+    /// Load a Hangouts.json file from its cache if it has a valid one, and otherwise parse it (see
+    /// `from_path`) and cache it for next time.
+    pub fn from_path_cached(path: impl AsRef<Path>) -> Result<Hangouts, Error> {
+        let path = path.as_ref();
+        if let Some(hangouts) = Hangouts::load_cache(path)? {
+            return Ok(hangouts);
+        }
+        let hangouts = Hangouts::from_path(path)?;
+        // The cache only saves time, so not being able to write one (to a read-only directory,
+        // say) isn't an error.
+        let _ = hangouts.save_cache(path);
+        Ok(hangouts)
+    }
+}
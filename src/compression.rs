@@ -0,0 +1,101 @@
+//! Reading compressed copies of Hangouts.json.
+//!
+//! The compression format is detected from the first few bytes of the input rather than the file
+//! name. Each format needs its own cargo feature: `gzip`, `bzip2`, `zstd`, or `xz`.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::raw::Hangouts;
+use crate::stream::ConversationStream;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Identify the compression format from the start of a file.
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if magic.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    /// The cargo feature needed to decompress this format.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Bzip2 => Some("bzip2"),
+            Compression::Zstd => Some("zstd"),
+            Compression::Xz => Some("xz"),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::None => "uncompressed",
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        })
+    }
+}
+
+/// Wrap a reader in whatever decompressor its contents need. Fails if the format's feature isn't
+/// enabled.
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    match Compression::detect(reader.fill_buf()?) {
+        Compression::None => Ok(Box::new(reader)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Ok(Box::new(bzip2::bufread::MultiBzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::with_buffer(reader)?)),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader))),
+        #[allow(unreachable_patterns)]
+        other => Err(io::Error::new(io::ErrorKind::Unsupported, format!(
+            "input is {}-compressed, which needs the `{}` feature",
+            other, other.feature().unwrap_or_default()))),
+    }
+}
+
+/// Open a file, decompressing it if necessary.
+pub fn open(path: impl AsRef<Path>) -> io::Result<Box<dyn Read>> {
+    decompress(BufReader::new(File::open(path)?))
+}
+
+impl Hangouts {
+    /// Parse a whole Hangouts.json file, which may be compressed.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Hangouts, Error> {
+        Hangouts::from_reader(open(path)?)
+    }
+
+    /// Parse a Hangouts.json file, which may be compressed, one conversation at a time.
+    pub fn stream_path(path: impl AsRef<Path>)
+        -> Result<ConversationStream<BufReader<Box<dyn Read>>>, Error>
+    {
+        Ok(Hangouts::stream_conversations(open(path)?))
+    }
+}
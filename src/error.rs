@@ -0,0 +1,81 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+
+    /// The JSON was malformed or didn't match the expected structure.
+    Json {
+        /// Location of the failing node, e.g.
+        /// `conversations[512].events[33].chat_message.message_content.segment[2]`.
+        path: String,
+        /// The conversation the failing node belonged to, if it could be determined.
+        conversation_id: Option<String>,
+        source: serde_json::Error,
+    },
+
+    /// A timestamp string that isn't a number of microseconds since the epoch, or a timestamp
+    /// that's out of the range of the type it's being converted to.
+    Timestamp {
+        value: String,
+    },
+
+    /// The data parsed, but doesn't make sense.
+    Validation {
+        conversation_id: Option<String>,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Json { path, conversation_id, source } => {
+                write!(f, "JSON error at {}: {}", path, source)?;
+                if let Some(id) = conversation_id {
+                    write!(f, " (in conversation {})", id)?;
+                }
+                Ok(())
+            }
+            Error::Timestamp { value } => write!(f, "invalid timestamp {:?}", value),
+            Error::Validation { conversation_id, message } => {
+                write!(f, "invalid data: {}", message)?;
+                if let Some(id) = conversation_id {
+                    write!(f, " (in conversation {})", id)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json { source, .. } => Some(source),
+            Error::Timestamp { .. } | Error::Validation { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return Error::Io(e.into());
+        }
+        Error::Json {
+            path: ".".to_owned(),
+            conversation_id: None,
+            source: e,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Exporting conversations in formats meant for reading.
+
+pub mod html;
+pub mod site;
@@ -0,0 +1,270 @@
+//! Conversations as standalone HTML documents.
+//!
+//! Each event is a line: messages with the time and the sender's name, coloured so that it's easy
+//! to follow who's talking, and calls, renames, and people joining or leaving as lines of their
+//! own. A heading separates the days. Photos are shown inline, from local copies if the
+//! `Exporter` is told where to find them, and otherwise from Google's servers.
+
+use std::io;
+
+use crate::model::{Attachment, CallKind, Conversation, Event, EventKind, Participant, SystemEvent};
+use crate::raw::MembershipChangeType;
+use crate::render::{self, escape_html, Html, Link, Renderer, Style};
+use crate::timestamp::Civil;
+
+/// The styles used by exported documents, which are included in each one.
+pub const STYLESHEET: &str = "\
+body { font-family: sans-serif; max-width: 50em; margin: 0 auto; padding: 1em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.participants { color: #666; margin-top: 0; }
+.day { font-size: 1em; color: #666; border-bottom: 1px solid #ddd; padding-top: 1em; }
+.event { margin: 0.3em 0; padding-left: 3.5em; text-indent: -3.5em; overflow-wrap: anywhere; }
+.event time { display: inline-block; width: 3.5em; text-indent: 0; color: #999; }
+.sender { font-weight: bold; }
+.system { color: #666; font-style: italic; }
+.event img, .event video { display: block; max-width: 100%; max-height: 30em; margin: 0.3em 0; }
+";
+
+type AttachmentUrl<'a> = Box<dyn Fn(&Attachment) -> Option<String> + 'a>;
+
+/// Turns conversations into HTML.
+#[derive(Default)]
+pub struct Exporter<'a> {
+    attachment_url: Option<AttachmentUrl<'a>>,
+    pub(crate) utc_offset: i32,
+    /// Put in front of relative attachment URLs, for pages that aren't in the directory the URLs
+    /// are relative to.
+    pub(crate) root: String,
+}
+
+impl<'a> Exporter<'a> {
+    pub fn new() -> Self {
+        Exporter::default()
+    }
+
+    /// Where to find local copies of attachments: the function gives the URL of an attachment's
+    /// file, relative to the document or absolute, or `None` if there isn't one. An
+    /// `AttachmentResolver` can be used to find them.
+    pub fn attachment_urls(mut self, url: impl Fn(&Attachment) -> Option<String> + 'a) -> Self {
+        self.attachment_url = Some(Box::new(url));
+        self
+    }
+
+    /// Show times in a time zone this many seconds ahead of UTC, instead of in UTC.
+    pub fn utc_offset(mut self, seconds: i32) -> Self {
+        self.utc_offset = seconds;
+        self
+    }
+
+    /// A whole HTML document for the conversation.
+    pub fn conversation(&self, convo: &Conversation) -> String {
+        let mut body = String::new();
+        body.push_str(&format!("<h1>{}</h1>\n", escape_html(&convo.display_name())));
+        body.push_str(&format!("<p class=\"participants\">{}</p>\n", participant_list(convo)));
+        self.events(&mut body, &convo.events);
+        page(&convo.display_name(), "", &body)
+    }
+
+    pub fn write_conversation(&self, convo: &Conversation, mut out: impl io::Write)
+        -> io::Result<()>
+    {
+        out.write_all(self.conversation(convo).as_bytes())
+    }
+
+    /// The events alone, as an HTML fragment, with a heading at the start of each day.
+    pub fn events(&self, out: &mut String, events: &[Event]) {
+        let mut day = None;
+        for event in events {
+            let time = event.timestamp.civil(self.utc_offset);
+            if day != Some(time.date()) {
+                day = Some(time.date());
+                out.push_str(&format!("<h2 class=\"day\">{:04}-{:02}-{:02}</h2>\n",
+                    time.year, time.month, time.day));
+            }
+            self.event(out, event, time);
+        }
+    }
+
+    fn event(&self, out: &mut String, event: &Event, time: Civil) {
+        let system = !matches!(event.kind, EventKind::Message(_));
+        out.push_str(&format!(
+            "<div class=\"event{}\" id=\"{}\"><time datetime=\"{}\">{:02}:{:02}</time> ",
+            if system { " system" } else { "" },
+            escape_html(&event.id), event.timestamp, time.hour, time.minute));
+        let sender = &event.sender;
+        let renderer = PageRenderer { exporter: self, sender };
+        match event.kind {
+            EventKind::Message(ref msg) => {
+                out.push_str(&renderer.render_message(sender.display_name(), &msg.body()));
+            }
+            EventKind::Call(ref call) => {
+                let line = match call.kind {
+                    CallKind::Started => format!("{} started a call", name(sender)),
+                    CallKind::Ended { duration } => {
+                        format!("Call ended after {}", format_duration(duration.as_secs()))
+                    }
+                    CallKind::Missed => format!("Missed call from {}", name(sender)),
+                    CallKind::Voicemail { duration, ref transcript } => {
+                        let mut line = format!("Voicemail from {} ({})", name(sender),
+                            format_duration(duration.as_secs()));
+                        if let Some(transcript) = transcript {
+                            line += &format!(": &ldquo;{}&rdquo;", escape_html(transcript));
+                        }
+                        line
+                    }
+                    CallKind::Unknown(_) => "Unknown call event".to_owned(),
+                };
+                out.push_str(&line);
+            }
+            EventKind::System(SystemEvent::Rename { ref old_name, ref new_name }) => {
+                if old_name.is_empty() {
+                    out.push_str(&format!("{} named the conversation &ldquo;{}&rdquo;",
+                        name(sender), escape_html(new_name)));
+                } else {
+                    out.push_str(&format!(
+                        "{} renamed the conversation from &ldquo;{}&rdquo; to &ldquo;{}&rdquo;",
+                        name(sender), escape_html(old_name), escape_html(new_name)));
+                }
+            }
+            EventKind::System(SystemEvent::MembershipChange { ref kind, ref participants }) => {
+                let names = participants.iter().map(name).collect::<Vec<_>>().join(", ");
+                let by_themselves = participants.iter().all(|p| p.id == sender.id);
+                let line = match kind {
+                    MembershipChangeType::Join if by_themselves => format!("{} joined", names),
+                    MembershipChangeType::Join => format!("{} added {}", name(sender), names),
+                    MembershipChangeType::Leave if by_themselves => format!("{} left", names),
+                    MembershipChangeType::Leave => format!("{} removed {}", name(sender), names),
+                    MembershipChangeType::Other(other) => {
+                        format!("Membership change ({}): {}", escape_html(other), names)
+                    }
+                };
+                out.push_str(&line);
+            }
+            EventKind::Unknown(_) => out.push_str("Unknown event"),
+        }
+        out.push_str("</div>\n");
+    }
+}
+
+/// A complete document around an HTML fragment, with the stylesheet and anything else given for
+/// the `<head>`.
+pub(crate) fn page(title: &str, head: &str, body: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+        <title>{}</title>\n<style>\n{}</style>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title), STYLESHEET, head, body)
+}
+
+pub(crate) fn participant_list(convo: &Conversation) -> String {
+    convo.participants.iter().map(name).collect::<Vec<_>>().join(", ")
+}
+
+/// A participant's name, in their colour.
+pub(crate) fn name(participant: &Participant) -> String {
+    format!("<span class=\"sender\" style=\"color: {}\">{}</span>",
+        colour(participant), escape_html(participant.display_name()))
+}
+
+/// A colour for a participant, which is always the same for the same person, and dark enough to
+/// read on white.
+fn colour(participant: &Participant) -> String {
+    // FNV-1a, since the standard library's hashers aren't guaranteed to be stable.
+    let mut hash: u32 = 0x811c_9dc5;
+    for b in participant.id.gaia_id.bytes().chain(participant.id.chat_id.bytes()) {
+        hash = (hash ^ u32::from(b)).wrapping_mul(0x0100_0193);
+    }
+    // Similar IDs hash similarly, so mix the bits up before picking a hue.
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    format!("hsl({}, 70%, 35%)", hash % 360)
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// `Html`, but with the sender's name coloured, and attachments shown from their local copies
+/// where possible.
+struct PageRenderer<'a> {
+    exporter: &'a Exporter<'a>,
+    sender: &'a Participant,
+}
+
+impl Renderer for PageRenderer<'_> {
+    fn text(&self, out: &mut String, text: &str, style: Style) {
+        Html.text(out, text, style);
+    }
+
+    fn link(&self, out: &mut String, link: &Link<'_>) {
+        Html.link(out, link);
+    }
+
+    fn line_break(&self, out: &mut String) {
+        Html.line_break(out);
+    }
+
+    fn attachment(&self, out: &mut String, attachment: &Attachment) {
+        let local = self.exporter.attachment_url.as_ref()
+            .and_then(|url| url(attachment))
+            .map(|url| {
+                if is_relative(&url) { format!("{}{}", self.exporter.root, url) } else { url }
+            });
+        let local = local.as_deref().map(escape_html);
+        match (attachment, local) {
+            (Attachment::Photo(_), Some(src)) => out.push_str(&image(&src, &src, "photo")),
+            (Attachment::Photo(photo), None)
+                if render::is_safe_url(&photo.url) && render::is_safe_url(&photo.thumbnail.url) =>
+            {
+                let (url, thumbnail) = (escape_html(&photo.url), escape_html(&photo.thumbnail.url));
+                out.push_str(&image(&url, &thumbnail, "photo"));
+            }
+            (Attachment::Video(_), Some(src)) => {
+                out.push_str(&format!("<video src=\"{}\" controls preload=\"metadata\"></video>",
+                    src));
+            }
+            (Attachment::Audio(_), Some(src)) => {
+                out.push_str(&format!("<audio src=\"{}\" controls preload=\"metadata\"></audio>",
+                    src));
+            }
+            (Attachment::File(file), Some(src)) if is_image(&file.original_name) => {
+                out.push_str(&image(&src, &src, &escape_html(&file.original_name)));
+            }
+            (Attachment::File(file), Some(src)) => {
+                out.push_str(&format!("<a href=\"{}\">{}</a>",
+                    src, escape_html(&file.original_name)));
+            }
+            _ => Html.attachment(out, attachment),
+        }
+        out.push('\n');
+    }
+
+    fn sender(&self, out: &mut String, _name: &str) {
+        out.push_str(&name(self.sender));
+        out.push_str(": ");
+    }
+
+    fn action(&self, out: &mut String, _name: &str) {
+        out.push_str("* ");
+        out.push_str(&name(self.sender));
+        out.push(' ');
+    }
+}
+
+/// An image linking to the full-size one. Everything should already be escaped.
+fn image(href: &str, src: &str, alt: &str) -> String {
+    format!("<a href=\"{}\"><img src=\"{}\" alt=\"{}\" loading=\"lazy\"></a>", href, src, alt)
+}
+
+fn is_relative(url: &str) -> bool {
+    !url.starts_with('/') && !url.split('/').next().unwrap_or_default().contains(':')
+}
+
+fn is_image(name: &str) -> bool {
+    let extension = name.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    ["jpg", "jpeg", "png", "gif", "webp", "bmp"].contains(&extension.as_str())
+}
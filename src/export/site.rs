@@ -0,0 +1,429 @@
+//! A whole archive as a static website, for browsing in a web browser straight from disk.
+//!
+//! The site is laid out as:
+//!
+//! * `index.html`: every conversation, with its participants, how many messages it has, and when
+//!   it started and ended, most recent first; and a search box.
+//! * `conversations/<id>/index.html`: the months a conversation has messages in, and
+//!   `conversations/<id>/<year>-<month>.html` the events of each of them.
+//! * `participants/<id>.html`: each person, and the conversations they're in.
+//! * `search.js`: an index of the words in every message, which the search box loads as a script,
+//!   since browsers don't let pages opened from `file://` URLs fetch other files.
+//!
+//! Events are shown as by `export::html`, and attachment URLs relative to the site's directory
+//! work on every page.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::export::html::{self, Exporter};
+use crate::interner::{Interner, ParticipantHandle};
+use crate::model::{Archive, Conversation, Event, EventKind, Message, Participant};
+use crate::render::{escape_html, percent_encode};
+use crate::text::{LinkStyle, Newlines, TextOptions};
+use crate::Error;
+
+const SITE_STYLESHEET: &str = "<style>
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; vertical-align: top; padding: 0.3em 0.5em; }
+td { border-bottom: 1px solid #eee; }
+td.count { text-align: right; }
+nav { margin: 1em 0; color: #666; }
+#search { width: 100%; font-size: 1.1em; padding: 0.3em; box-sizing: border-box; }
+#results li { margin: 0.5em 0; }
+#results .context { color: #666; font-size: 0.9em; }
+</style>
+";
+
+/// Runs in the index page; see `SearchIndex` for the data it works with.
+const SEARCH_SCRIPT: &str = r#"<script>
+(function () {
+    var input = document.getElementById("search");
+    var status = document.getElementById("status");
+    var results = document.getElementById("results");
+    var loading = false;
+    var terms = null;
+
+    function words(text) {
+        return text.toLowerCase().match(/[\p{Alphabetic}\p{N}]+/gu) || [];
+    }
+
+    // Messages with every word of the query, taking the last word as a prefix since it may still
+    // be being typed.
+    function find(query) {
+        var found = null;
+        query.forEach(function (word, i) {
+            var docs = {};
+            var matching = i === query.length - 1
+                ? terms.filter(function (term) { return term.lastIndexOf(word, 0) === 0; })
+                : (SEARCH.terms.hasOwnProperty(word) ? [word] : []);
+            matching.forEach(function (term) {
+                SEARCH.terms[term].forEach(function (doc) { docs[doc] = true; });
+            });
+            found = found === null ? docs : Object.keys(found).reduce(function (both, doc) {
+                if (docs[doc]) { both[doc] = true; }
+                return both;
+            }, {});
+        });
+        return Object.keys(found || {}).map(Number).sort(function (a, b) { return a - b; });
+    }
+
+    function search() {
+        if (typeof SEARCH === "undefined") {
+            if (!loading) {
+                loading = true;
+                status.textContent = "Loading…";
+                var script = document.createElement("script");
+                script.src = "search.js";
+                script.onload = search;
+                document.head.appendChild(script);
+            }
+            return;
+        }
+        if (terms === null) {
+            terms = Object.keys(SEARCH.terms);
+        }
+        results.textContent = "";
+        var query = words(input.value);
+        if (query.length === 0) {
+            status.textContent = "";
+            return;
+        }
+        var found = find(query);
+        status.textContent = found.length + (found.length === 1 ? " message" : " messages")
+            + (found.length > 200 ? "; showing the first 200" : "");
+        found.slice(0, 200).forEach(function (i) {
+            var doc = SEARCH.docs[i];
+            var item = document.createElement("li");
+            var link = document.createElement("a");
+            link.href = doc[1];
+            link.textContent = doc[2] + ": " + doc[4];
+            var context = document.createElement("div");
+            context.className = "context";
+            context.textContent = SEARCH.conversations[doc[0]] + ", " + doc[3];
+            item.appendChild(link);
+            item.appendChild(context);
+            results.appendChild(item);
+        });
+    }
+
+    input.addEventListener("input", search);
+    if (input.value) {
+        search();
+    }
+})();
+</script>
+"#;
+
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July",
+    "August", "September", "October", "November", "December"];
+
+/// Writes an archive out as a static site.
+pub struct Site<'a> {
+    exporter: Exporter<'a>,
+    title: String,
+}
+
+impl<'a> Site<'a> {
+    /// A site whose conversations are shown by the given exporter.
+    pub fn new(mut exporter: Exporter<'a>) -> Self {
+        // Conversations' pages are two directories down.
+        exporter.root = "../../".to_owned();
+        Site { exporter, title: "Hangouts".to_owned() }
+    }
+
+    /// The title of the index page.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Write the site into the given directory, creating it if need be. Existing files with the
+    /// same names are overwritten.
+    pub fn write(&self, archive: &Archive, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir.join("conversations"))?;
+        fs::create_dir_all(dir.join("participants"))?;
+
+        let mut conversations = archive.conversations.iter().collect::<Vec<_>>();
+        conversations.sort_by_key(|convo| Reverse(convo.events.last().map(|e| e.timestamp)));
+        let slugs = unique_slugs(conversations.iter().map(|convo| convo.id.as_str()));
+        let people = People::new(&conversations);
+
+        let mut search = SearchIndex::default();
+        for (convo, slug) in conversations.iter().zip(&slugs) {
+            self.write_conversation(dir, convo, slug, &people, &mut search)?;
+        }
+        for (handle, participant) in people.participants.iter().enumerate() {
+            self.write_participant(dir, handle, participant, &conversations, &slugs, &people)?;
+        }
+        fs::write(dir.join("search.js"), search.to_script())?;
+        fs::write(dir.join("index.html"), self.index(&conversations, &slugs, &people))?;
+        Ok(())
+    }
+
+    fn index(&self, conversations: &[&Conversation], slugs: &[String], people: &People<'_>)
+        -> String
+    {
+        let mut body = format!("<h1>{}</h1>\n", escape_html(&self.title));
+        body.push_str("<input id=\"search\" type=\"search\" placeholder=\"Search messages\" \
+            autocomplete=\"off\">\n<p id=\"status\"></p>\n<ol id=\"results\"></ol>\n");
+        body.push_str("<table>\n<tr><th>Conversation</th><th>Participants</th>\
+            <th>Messages</th><th>Dates</th></tr>\n");
+        for (convo, slug) in conversations.iter().zip(slugs) {
+            body.push_str(&format!(
+                "<tr><td><a href=\"conversations/{}/index.html\">{}</a></td><td>{}</td>\
+                <td class=\"count\">{}</td><td>{}</td></tr>\n",
+                slug, escape_html(&convo.display_name()),
+                people.links(&convo.participants, ""),
+                convo.messages().count(), self.date_range(convo)));
+        }
+        body.push_str("</table>\n");
+        body.push_str(SEARCH_SCRIPT);
+        html::page(&self.title, SITE_STYLESHEET, &body)
+    }
+
+    fn write_conversation(
+        &self,
+        dir: &Path,
+        convo: &Conversation,
+        slug: &str,
+        people: &People<'_>,
+        search: &mut SearchIndex,
+    ) -> Result<(), Error> {
+        let convo_dir = dir.join("conversations").join(slug);
+        fs::create_dir_all(&convo_dir)?;
+        let name = convo.display_name();
+        let months = self.months(convo);
+        let conversation_index = search.conversations.len();
+        search.conversations.push(name.clone());
+
+        let mut body = format!("<nav><a href=\"../../index.html\">All conversations</a></nav>\n\
+            <h1>{}</h1>\n<p class=\"participants\">{}</p>\n<ul>\n",
+            escape_html(&name), people.links(&convo.participants, "../../"));
+        for &((year, month), ref range) in &months {
+            let count = convo.events[range.clone()].iter()
+                .filter(|e| matches!(e.kind, EventKind::Message(_)))
+                .count();
+            body.push_str(&format!("<li><a href=\"{}\">{} {}</a> ({} {})</li>\n",
+                month_file(year, month), MONTHS[month as usize - 1], year, count,
+                if count == 1 { "message" } else { "messages" }));
+        }
+        body.push_str("</ul>\n");
+        fs::write(convo_dir.join("index.html"), html::page(&name, SITE_STYLESHEET, &body))?;
+
+        for (i, &((year, month), ref range)) in months.iter().enumerate() {
+            let title = format!("{}: {} {}", name, MONTHS[month as usize - 1], year);
+            let mut nav = String::from("<nav>");
+            if let Some(((year, month), _)) = i.checked_sub(1).map(|i| &months[i]) {
+                nav.push_str(&format!("<a href=\"{}\">&larr; {} {}</a> | ",
+                    month_file(*year, *month), MONTHS[*month as usize - 1], year));
+            }
+            nav.push_str("<a href=\"index.html\">All months</a>");
+            if let Some(((year, month), _)) = months.get(i + 1) {
+                nav.push_str(&format!(" | <a href=\"{}\">{} {} &rarr;</a>",
+                    month_file(*year, *month), MONTHS[*month as usize - 1], year));
+            }
+            nav.push_str("</nav>\n");
+
+            let mut body = format!("{}<h1>{}</h1>\n", nav, escape_html(&title));
+            let events = &convo.events[range.clone()];
+            self.exporter.events(&mut body, events);
+            body.push_str(&nav);
+            let file = month_file(year, month);
+            fs::write(convo_dir.join(&file), html::page(&title, SITE_STYLESHEET, &body))?;
+
+            let url = format!("conversations/{}/{}", slug, file);
+            search.add(conversation_index, &url, events.iter().filter_map(|event| {
+                match event.kind {
+                    EventKind::Message(ref msg) => Some((event, msg)),
+                    _ => None,
+                }
+            }), self.exporter.utc_offset);
+        }
+        Ok(())
+    }
+
+    fn write_participant(
+        &self,
+        dir: &Path,
+        handle: usize,
+        participant: &Participant,
+        conversations: &[&Conversation],
+        slugs: &[String],
+        people: &People<'_>,
+    ) -> Result<(), Error> {
+        let name = participant.display_name();
+        let mut body = format!("<nav><a href=\"../index.html\">All conversations</a></nav>\n\
+            <h1>{}</h1>\n", html::name(participant));
+        if let Some(ref phone) = participant.phone_number {
+            body.push_str(&format!("<p>{}</p>\n", escape_html(phone)));
+        }
+        body.push_str("<table>\n<tr><th>Conversation</th><th>Messages from them</th>\
+            <th>Dates</th></tr>\n");
+        for (convo, slug) in conversations.iter().zip(slugs) {
+            if !convo.participants.iter().any(|p| people.handle(p) == Some(handle)) {
+                continue;
+            }
+            let sent = convo.messages()
+                .filter(|(event, _)| people.handle(&event.sender) == Some(handle))
+                .count();
+            body.push_str(&format!(
+                "<tr><td><a href=\"../conversations/{}/index.html\">{}</a></td>\
+                <td class=\"count\">{}</td><td>{}</td></tr>\n",
+                slug, escape_html(&convo.display_name()), sent, self.date_range(convo)));
+        }
+        body.push_str("</table>\n");
+        let path = dir.join("participants").join(format!("{}.html", people.slugs[handle]));
+        fs::write(path, html::page(name, SITE_STYLESHEET, &body))?;
+        Ok(())
+    }
+
+    /// The conversation's events, split up by month.
+    fn months(&self, convo: &Conversation) -> Vec<((i64, u32), Range<usize>)> {
+        let mut months: Vec<((i64, u32), Range<usize>)> = vec![];
+        for (i, event) in convo.events.iter().enumerate() {
+            let time = event.timestamp.civil(self.exporter.utc_offset);
+            match months.last_mut() {
+                Some((month, range)) if *month == (time.year, time.month) => range.end = i + 1,
+                _ => months.push(((time.year, time.month), i..i + 1)),
+            }
+        }
+        months
+    }
+
+    fn date_range(&self, convo: &Conversation) -> String {
+        let date = |event: &Event| {
+            let time = event.timestamp.civil(self.exporter.utc_offset);
+            format!("{:04}-{:02}-{:02}", time.year, time.month, time.day)
+        };
+        match (convo.events.first(), convo.events.last()) {
+            (Some(first), Some(last)) if date(first) == date(last) => date(first),
+            (Some(first), Some(last)) => format!("{}&nbsp;&ndash; {}", date(first), date(last)),
+            _ => String::new(),
+        }
+    }
+}
+
+fn month_file(year: i64, month: u32) -> String {
+    format!("{:04}-{:02}.html", year, month)
+}
+
+/// File names for IDs, keeping only the characters that are safe everywhere, and numbering any
+/// that end up the same as one before them.
+fn unique_slugs<'a>(ids: impl Iterator<Item = &'a str>) -> Vec<String> {
+    // Compared ignoring case, since file systems often do.
+    let mut used = HashSet::new();
+    ids.map(|id| {
+        let mut base = id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect::<String>();
+        if base.is_empty() {
+            base.push('_');
+        }
+        let mut slug = base.clone();
+        let mut n = 1;
+        while !used.insert(slug.to_ascii_lowercase()) {
+            n += 1;
+            slug = format!("{}-{}", base, n);
+        }
+        slug
+    }).collect()
+}
+
+/// Everyone in any of the conversations, each once.
+struct People<'a> {
+    interner: Interner,
+    participants: Vec<&'a Participant>,
+    slugs: Vec<String>,
+}
+
+impl<'a> People<'a> {
+    fn new(conversations: &[&'a Conversation]) -> Self {
+        let mut interner = Interner::new();
+        let mut participants = vec![];
+        for participant in conversations.iter().flat_map(|convo| &convo.participants) {
+            if interner.intern_participant(&participant.id).index() == participants.len() {
+                participants.push(participant);
+            }
+        }
+        let slugs = unique_slugs(participants.iter().map(|p| {
+            if p.id.chat_id.is_empty() { p.id.gaia_id.as_str() } else { p.id.chat_id.as_str() }
+        }));
+        People { interner, participants, slugs }
+    }
+
+    fn handle(&self, participant: &Participant) -> Option<usize> {
+        self.interner.participant_handle(&participant.id).map(ParticipantHandle::index)
+    }
+
+    /// Links to the pages of the given participants, from a page `root` below the site's.
+    fn links(&self, participants: &[Participant], root: &str) -> String {
+        participants.iter()
+            .map(|p| match self.handle(p) {
+                Some(handle) => format!("<a href=\"{}participants/{}.html\">{}</a>",
+                    root, self.slugs[handle], html::name(p)),
+                None => html::name(p),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The words of every message, and which messages each is in.
+#[derive(Default, Serialize)]
+struct SearchIndex {
+    conversations: Vec<String>,
+    /// (conversation, URL, sender, date, text)
+    docs: Vec<(usize, String, String, String, String)>,
+    terms: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    fn add<'a>(
+        &mut self,
+        conversation: usize,
+        page: &str,
+        messages: impl Iterator<Item = (&'a Event, &'a Message)>,
+        utc_offset: i32,
+    ) {
+        let options = TextOptions::new()
+            .links(LinkStyle::Both)
+            .attachment_placeholder("[{type}]")
+            .newlines(Newlines::Spaces);
+        for (event, msg) in messages {
+            let text = msg.text_with(&options);
+            let doc = self.docs.len();
+            let mut words = words(&text);
+            words.sort_unstable();
+            words.dedup();
+            for word in words {
+                self.terms.entry(word).or_default().push(doc);
+            }
+            let time = event.timestamp.civil(utc_offset);
+            self.docs.push((
+                conversation,
+                format!("{}#{}", page, percent_encode(&event.id)),
+                event.sender.display_name().to_owned(),
+                format!("{:04}-{:02}-{:02} {:02}:{:02}",
+                    time.year, time.month, time.day, time.hour, time.minute),
+                text.into_owned(),
+            ));
+        }
+    }
+
+    fn to_script(&self) -> String {
+        format!("var SEARCH = {};\n", serde_json::to_string(self).expect("serializing to a string"))
+    }
+}
+
+/// Split text into lowercase words, the same way the search script does.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,293 @@
+//! The Google Chat part of Takeout, which replaced Hangouts.
+//!
+//! Each conversation ("group") is a folder under `Google Chat/Groups/`, named like `DM abc123` or
+//! `Space abc123`, holding `group_info.json`, `messages.json`, and any files uploaded to it.
+//! Conversations that were migrated from Hangouts include their Hangouts history as well, so
+//! `Archive::merge_google_chat` combines each one with the Hangouts conversation it came from.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use serde::de::{self, Deserialize, Deserializer, DeserializeOwned};
+
+use crate::model::{self, Attachment, Conversation, Event, EventKind, Participant, UploadedFile};
+use crate::raw::{self, Extra};
+use crate::{Error, Timestamp};
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct GroupInfo {
+    #[serde(default)] pub name: Option<String>,
+    #[serde(default)] pub members: Vec<User>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct User {
+    #[serde(default)] pub name: Option<String>,
+    #[serde(default)] pub email: Option<String>,
+    #[serde(default)] pub user_type: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+impl User {
+    /// Chat exports identify people only by name and email address, so these IDs have an empty
+    /// `gaia_id`, and the email address (or name, if there is none) as the `chat_id`.
+    pub fn participant_id(&self) -> raw::ParticipantId {
+        raw::ParticipantId {
+            gaia_id: String::new(),
+            chat_id: self.email.clone().or_else(|| self.name.clone()).unwrap_or_default(),
+            extra: Extra::new(),
+        }
+    }
+
+    pub fn participant(&self) -> Participant {
+        Participant { id: self.participant_id(), name: self.name.clone(), phone_number: None }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+struct Messages {
+    #[serde(default)] messages: Vec<Message>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Message {
+    #[serde(default)] pub creator: Option<User>,
+    #[serde(deserialize_with="chat_date")] pub created_date: Timestamp,
+    #[serde(default, deserialize_with="optional_chat_date")] pub updated_date: Option<Timestamp>,
+    #[serde(default)] pub text: String,
+    #[serde(default)] pub topic_id: Option<String>,
+    #[serde(default)] pub message_id: Option<String>,
+    #[serde(default)] pub annotations: Vec<serde_json::Value>,
+    #[serde(default)] pub reactions: Vec<serde_json::Value>,
+    #[serde(default)] pub quoted_message_metadata: Option<serde_json::Value>,
+    #[serde(default)] pub attached_files: Vec<AttachedFile>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct AttachedFile {
+    pub original_name: String,
+    pub export_name: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+/// One Chat conversation, as read from its folder.
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    /// The name of the group's folder, such as `DM abc123`.
+    pub id: String,
+    pub info: GroupInfo,
+    pub messages: Vec<Message>,
+}
+
+impl Group {
+    pub fn from_readers(id: impl Into<String>, info: impl Read, messages: impl Read)
+        -> Result<Self, Error>
+    {
+        let id = id.into();
+        let info = parse(&id, "group_info.json", info)?;
+        let messages = parse::<Messages>(&id, "messages.json", messages)?.messages;
+        Ok(Group { id, info, messages })
+    }
+
+    /// Read a group's folder. A missing group_info.json is treated as empty.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let id = path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        let messages = BufReader::new(File::open(path.join("messages.json"))?);
+        match File::open(path.join("group_info.json")) {
+            Ok(info) => Group::from_readers(id, BufReader::new(info), messages),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                Group::from_readers(id, &b"{}"[..], messages)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Read every group under the given `Google Chat` folder that has a messages.json, in order of
+/// their IDs.
+pub fn read_dir(path: impl AsRef<Path>) -> Result<Vec<Group>, Error> {
+    let mut dirs = vec![];
+    for entry in fs::read_dir(path.as_ref().join("Groups"))? {
+        let dir = entry?.path();
+        if dir.join("messages.json").is_file() {
+            dirs.push(dir);
+        }
+    }
+    dirs.sort();
+    dirs.iter().map(Group::from_dir).collect()
+}
+
+fn parse<T: DeserializeOwned>(id: &str, file: &str, reader: impl Read) -> Result<T, Error> {
+    serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_reader(reader))
+        .map_err(|e| {
+            let path = format!("{}: {}", file, e.path());
+            let source = e.into_inner();
+            if source.is_io() {
+                return Error::Io(source.into());
+            }
+            Error::Json { path, conversation_id: Some(id.to_owned()), source }
+        })
+}
+
+impl From<Group> for Conversation {
+    fn from(group: Group) -> Self {
+        let Group { id, info, messages } = group;
+        let mut events = messages.into_iter()
+            .enumerate()
+            .map(|(i, message)| message.into_event(&id, i))
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.timestamp);
+
+        let kind = if id.starts_with("DM ") {
+            raw::ConversationType::StickyOneToOne
+        } else {
+            raw::ConversationType::Group
+        };
+
+        Conversation {
+            kind,
+            name: info.name,
+            participants: info.members.iter().map(User::participant).collect(),
+            events,
+            id,
+        }
+    }
+}
+
+impl Message {
+    fn into_event(self, group_id: &str, index: usize) -> Event {
+        let sender = self.creator
+            .as_ref()
+            .map(User::participant)
+            .unwrap_or_else(unknown_participant);
+
+        let attachments = self.attached_files
+            .into_iter()
+            .map(|file| Attachment::File(UploadedFile {
+                original_name: file.original_name,
+                export_name: file.export_name,
+            }))
+            .collect();
+
+        Event {
+            id: self.message_id.unwrap_or_else(|| format!("{}/{}", group_id, index)),
+            timestamp: self.created_date,
+            sender,
+            medium: model::Medium::GoogleChat,
+            kind: EventKind::Message(model::Message::from_text(&self.text, attachments)),
+        }
+    }
+}
+
+fn unknown_participant() -> Participant {
+    Participant {
+        id: raw::ParticipantId { gaia_id: String::new(), chat_id: String::new(), extra: Extra::new() },
+        name: None,
+        phone_number: None,
+    }
+}
+
+impl model::Archive {
+    /// Add Google Chat conversations. Those which were migrated from a Hangouts conversation
+    /// (mostly the same people, and some of the same messages) are merged into it, without
+    /// duplicating the messages they have in common; the rest are added as new conversations.
+    pub fn merge_google_chat(&mut self, groups: Vec<Group>) {
+        for group in groups {
+            let chat = Conversation::from(group);
+            match self.conversations.iter_mut().find(|convo| is_migrated(convo, &chat)) {
+                Some(convo) => convo.absorb(chat),
+                None => {
+                    for p in &chat.participants {
+                        self.participants.entry(p.id.clone()).or_insert_with(|| p.clone());
+                    }
+                    self.conversations.push(chat);
+                }
+            }
+        }
+    }
+}
+
+fn member_names(convo: &Conversation) -> HashSet<String> {
+    convo.participants.iter()
+        .filter_map(|p| p.name.as_deref())
+        .map(model::normalize_name)
+        .collect()
+}
+
+/// Members can join or leave, or be renamed, after the migration, so it's enough for at least
+/// half of them to be the same.
+fn is_migrated(hangouts: &Conversation, chat: &Conversation) -> bool {
+    let (members, old_members) = (member_names(chat), member_names(hangouts));
+    let common = members.intersection(&old_members).count();
+    common > 0 && common * 2 >= members.len().max(old_members.len())
+        && hangouts.shares_history(chat)
+}
+
+fn chat_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_date(&s).ok_or_else(|| de::Error::custom(format!("invalid date {:?}", s)))
+}
+
+fn optional_chat_date<'de, D: Deserializer<'de>>(deserializer: D)
+    -> Result<Option<Timestamp>, D::Error>
+{
+    chat_date(deserializer).map(Some)
+}
+
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July",
+    "August", "September", "October", "November", "December"];
+
+/// Parse a date like "Friday, July 14, 2017 at 2:40:00 AM UTC". Newer exports have a narrow
+/// no-break space before the AM/PM.
+fn parse_date(s: &str) -> Option<Timestamp> {
+    let mut words = s.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty());
+    let _weekday = words.next()?;
+    let month = words.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+    let day = words.next()?.parse::<u32>().ok()?;
+    let year = words.next()?.parse().ok()?;
+    if words.next()? != "at" {
+        return None;
+    }
+
+    let mut time = words.next()?.split(':').map(|n| n.parse::<u32>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if hour == 0 || hour > 12 {
+        return None;
+    }
+    let hour = match (words.next()?, hour) {
+        ("AM", 12) => 0,
+        ("AM", h) | ("PM", h @ 12) => h,
+        ("PM", h) => h + 12,
+        _ => return None,
+    };
+    if words.next()? != "UTC" || words.next().is_some() || !(1..=31).contains(&day)
+        || minute > 59 || second > 60
+    {
+        return None;
+    }
+
+    // This also checks that the day exists in the month, and that the year is in range.
+    Timestamp::from_utc(year, month, day, hour * 3600 + minute * 60 + second)
+}
@@ -0,0 +1,594 @@
+//! The Google Voice part of Takeout, which has the SMS and call history that Hangouts only has
+//! part of.
+//!
+//! Every text thread and call is an HTML file in `Voice/Calls/`, named like
+//! `Alice - Text - 2017-07-14T02_40_00Z.html` or `+15551234567 - Missed - ....html`, and marked up
+//! with the hChat and hAudio microformats. These are collected into conversations keyed by the
+//! phone numbers of the people on the other end, so they can be merged with the Hangouts
+//! conversations for the same numbers.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::model::{self, Attachment, Call, CallKind, Conversation, Event, EventKind, Participant,
+    UploadedFile};
+use crate::raw;
+use crate::{Error, Timestamp};
+
+/// Conversations from a Voice export.
+#[derive(Debug, Default)]
+pub struct Voice {
+    /// Keyed by the normalized phone number of the other person, or for group threads, their
+    /// numbers sorted and separated by commas.
+    pub conversations: BTreeMap<String, Conversation>,
+}
+
+impl Voice {
+    /// Read every HTML file in a `Voice/Calls` folder. The `Voice` folder itself works too.
+    pub fn read_dir(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut path = path.as_ref().to_owned();
+        if path.join("Calls").is_dir() {
+            path.push("Calls");
+        }
+
+        let mut names = vec![];
+        for entry in fs::read_dir(&path)? {
+            if let Ok(name) = entry?.file_name().into_string() {
+                if name.ends_with(".html") {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+
+        let mut voice = Voice::default();
+        for name in names {
+            let html = fs::read_to_string(path.join(&name))?;
+            voice.add_file(&name, &html)?;
+        }
+        Ok(voice)
+    }
+
+    /// Add the contents of one file, given its name and contents. Files of kinds this doesn't
+    /// know about are ignored.
+    pub fn add_file(&mut self, file_name: &str, html: &str) -> Result<(), Error> {
+        let stem = file_name.strip_suffix(".html").unwrap_or(file_name);
+        let mut parts = stem.rsplitn(3, " - ").skip(1);
+        let (kind, contact) = match (parts.next(), parts.next()) {
+            (Some(kind), contact) => (kind, contact.unwrap_or_default()),
+            (None, _) => return Ok(()),
+        };
+
+        let invalid = |message: String| Error::Validation {
+            conversation_id: Some(file_name.to_owned()),
+            message,
+        };
+
+        let (key, participants, events) = match kind {
+            "Text" | "Group Conversation" => parse_texts(stem, contact, html).map_err(invalid)?,
+            "Received" | "Placed" | "Missed" | "Voicemail" => {
+                parse_call(stem, kind, contact, html).map_err(invalid)?
+            }
+            _ => return Ok(()),
+        };
+
+        let convo = self.conversations.entry(key.clone()).or_insert_with(|| Conversation {
+            kind: if key.contains(',') {
+                raw::ConversationType::Group
+            } else {
+                raw::ConversationType::StickyOneToOne
+            },
+            id: key,
+            name: None,
+            participants: vec![],
+            events: vec![],
+        });
+        for participant in participants {
+            if !convo.participants.contains(&participant) {
+                convo.participants.push(participant);
+            }
+        }
+        convo.events.extend(events);
+        convo.events.sort_by_key(|event| event.timestamp);
+        Ok(())
+    }
+}
+
+impl model::Archive {
+    /// Add Google Voice conversations. Those with the same phone numbers as a Hangouts
+    /// conversation are merged into it, without duplicating the messages they have in common;
+    /// the rest are added as new conversations.
+    pub fn merge_google_voice(&mut self, voice: Voice) {
+        for (key, mut voice_convo) in voice.conversations {
+            let numbers = key.split(',').map(str::to_owned).collect::<HashSet<_>>();
+            match self.conversations.iter_mut().find(|convo| phone_numbers(convo) == numbers) {
+                Some(convo) => {
+                    // Your own number isn't in Hangouts, but you're the one without one.
+                    let mut unnumbered = convo.participants.iter().filter(|p| p.phone_number.is_none());
+                    if let (Some(me), None) = (unnumbered.next(), unnumbered.next()) {
+                        for event in &mut voice_convo.events {
+                            if !event.sender.phone_number.as_ref().is_some_and(|n| numbers.contains(n)) {
+                                event.sender = me.clone();
+                            }
+                        }
+                    }
+                    convo.absorb(voice_convo);
+                }
+                None => {
+                    for p in &voice_convo.participants {
+                        self.participants.entry(p.id.clone()).or_insert_with(|| p.clone());
+                    }
+                    self.conversations.push(voice_convo);
+                }
+            }
+        }
+    }
+}
+
+fn phone_numbers(convo: &Conversation) -> HashSet<String> {
+    convo.participants.iter()
+        .filter_map(|p| p.phone_number.as_deref())
+        .filter_map(normalize_phone_number)
+        .collect()
+}
+
+/// Put a phone number into E.164 format, e.g. "+15551234567". Numbers without a country code are
+/// assumed to be North American, since Google Voice is only available there. Short codes are
+/// left as bare digits. Returns None if there are no digits, or there are letters.
+pub fn normalize_phone_number(number: &str) -> Option<String> {
+    let number = number.trim();
+    let number = number.strip_prefix("tel:").unwrap_or(number);
+    if number.chars().any(char::is_alphabetic) {
+        return None;
+    }
+    let digits = number.chars().filter(char::is_ascii_digit).collect::<String>();
+    match (number.starts_with('+'), digits.len()) {
+        (_, 0) => None,
+        (true, _) => Some(format!("+{}", digits)),
+        (false, 10) => Some(format!("+1{}", digits)),
+        (false, 11) if digits.starts_with('1') => Some(format!("+{}", digits)),
+        (false, _) => Some(digits),
+    }
+}
+
+/// Someone named in a file: the sender of a message, a member of a group, or the other end of a
+/// call.
+#[derive(Debug, Default)]
+struct Person {
+    name: Option<String>,
+    number: Option<String>,
+    /// The owner of the account. Their name is marked up differently.
+    is_self: bool,
+}
+
+impl Person {
+    fn participant(&self) -> Participant {
+        let chat_id = self.number.clone().or_else(|| self.name.clone()).unwrap_or_default();
+        Participant {
+            id: raw::ParticipantId { gaia_id: String::new(), chat_id, extra: raw::Extra::new() },
+            name: self.name.clone(),
+            phone_number: self.number.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Text {
+    timestamp: Option<String>,
+    sender: Person,
+    text: String,
+    files: Vec<String>,
+}
+
+type Parsed = (String, Vec<Participant>, Vec<Event>);
+
+fn parse_texts(stem: &str, contact: &str, html: &str) -> Result<Parsed, String> {
+    let mut members: Vec<Person> = vec![];
+    let mut texts: Vec<Text> = vec![];
+    let mut in_members = false;
+    let mut in_sender = false;
+    let mut in_quote = false;
+    let mut in_name = None;
+
+    for token in tokenize(html) {
+        match token {
+            Token::Start { ref name, ref attrs } => {
+                let class = |class| has_class(attrs, class);
+                match name.as_str() {
+                    "div" if class("participants") => in_members = true,
+                    "div" if class("message") => {
+                        in_members = false;
+                        texts.push(Text::default());
+                    }
+                    "cite" if class("sender") => {
+                        in_sender = true;
+                        if in_members {
+                            members.push(Person::default());
+                        }
+                    }
+                    "a" if class("tel") && in_sender => {
+                        let number = attr(attrs, "href").and_then(normalize_phone_number);
+                        match texts.last_mut() {
+                            Some(text) if !in_members => text.sender.number = number,
+                            _ => if let Some(member) = members.last_mut() {
+                                member.number = number;
+                            },
+                        }
+                    }
+                    "span" | "abbr" if class("fn") && in_sender => {
+                        in_name = Some(String::new());
+                        if name == "abbr" && !in_members {
+                            if let Some(text) = texts.last_mut() {
+                                text.sender.is_self = true;
+                            }
+                        }
+                    }
+                    "abbr" if class("dt") => {
+                        if let Some(text) = texts.last_mut() {
+                            text.timestamp = attr(attrs, "title").map(str::to_owned);
+                        }
+                    }
+                    "q" => in_quote = true,
+                    "br" if in_quote => {
+                        if let Some(text) = texts.last_mut() {
+                            text.text.push('\n');
+                        }
+                    }
+                    "img" | "audio" | "video" | "a" => {
+                        let src = attr(attrs, "src").or_else(|| {
+                            attr(attrs, "href").filter(|_| class("video") || class("audio"))
+                        });
+                        if let (Some(src), Some(text)) = (src, texts.last_mut()) {
+                            text.files.push(src.to_owned());
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Token::End { ref name } => match name.as_str() {
+                "cite" => in_sender = false,
+                "q" => in_quote = false,
+                "div" => in_members = false,
+                "span" | "abbr" => if let Some(name) = in_name.take() {
+                    let name = Some(name.trim().to_owned()).filter(|name| !name.is_empty());
+                    match texts.last_mut() {
+                        Some(text) if !in_members => text.sender.name = name,
+                        _ => if let Some(member) = members.last_mut() {
+                            member.name = name;
+                        },
+                    }
+                },
+                _ => (),
+            },
+            Token::Text(ref s) => {
+                if let Some(ref mut name) = in_name {
+                    name.push_str(s);
+                } else if in_quote {
+                    if let Some(text) = texts.last_mut() {
+                        text.text.push_str(s);
+                    }
+                }
+            }
+        }
+    }
+
+    // The other people are the group's members, or failing that, whoever sent messages.
+    let mut others = members.iter().filter(|p| !p.is_self).collect::<Vec<_>>();
+    if others.is_empty() {
+        let mut seen = HashSet::new();
+        others = texts.iter()
+            .map(|text| &text.sender)
+            .filter(|p| !p.is_self && seen.insert(&p.number))
+            .collect();
+    }
+    let mut numbers = others.iter().filter_map(|p| p.number.clone()).collect::<Vec<_>>();
+    if numbers.is_empty() {
+        numbers.extend(normalize_phone_number(contact));
+    }
+    numbers.sort();
+    numbers.dedup();
+    if numbers.is_empty() {
+        return Err("can't tell who the messages were with".to_owned());
+    }
+
+    let mut participants = others.iter().map(|p| p.participant()).collect::<Vec<_>>();
+    let me = texts.iter().find(|text| text.sender.is_self).map(|text| &text.sender);
+    if let Some(me) = me {
+        participants.push(me.participant());
+    }
+    let medium = model::Medium::GoogleVoice { self_phone: me.and_then(|me| me.number.clone()) };
+
+    let events = texts.into_iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let timestamp = text.timestamp.as_deref()
+                .and_then(parse_datetime)
+                .ok_or_else(|| format!("message {} has a missing or invalid timestamp", i))?;
+            let attachments = text.files.into_iter()
+                .map(|file| Attachment::File(UploadedFile { original_name: file.clone(), export_name: file }))
+                .collect();
+            Ok(Event {
+                id: format!("{}#{}", stem, i),
+                timestamp,
+                sender: text.sender.participant(),
+                medium: medium.clone(),
+                kind: EventKind::Message(model::Message::from_text(text.text.trim(), attachments)),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok((numbers.join(","), participants, events))
+}
+
+fn parse_call(stem: &str, kind: &str, contact: &str, html: &str) -> Result<Parsed, String> {
+    let mut other = Person::default();
+    let mut timestamp = None;
+    let mut duration = None;
+    let mut transcript = None;
+    let mut in_tel = false;
+    let mut in_transcript = false;
+    let mut in_name = None;
+
+    for token in tokenize(html) {
+        match token {
+            Token::Start { ref name, ref attrs } => {
+                let class = |class| has_class(attrs, class);
+                match name.as_str() {
+                    "a" if class("tel") => {
+                        in_tel = true;
+                        other.number = attr(attrs, "href").and_then(normalize_phone_number);
+                    }
+                    "span" if class("fn") && in_tel => in_name = Some(String::new()),
+                    "span" if class("full-text") => {
+                        in_transcript = true;
+                        transcript = Some(String::new());
+                    }
+                    "abbr" if class("published") => {
+                        timestamp = attr(attrs, "title").and_then(parse_datetime);
+                    }
+                    "abbr" if class("duration") => {
+                        duration = attr(attrs, "title").and_then(parse_duration);
+                    }
+                    _ => (),
+                }
+            }
+            Token::End { ref name } => match name.as_str() {
+                "a" => in_tel = false,
+                "span" => if let Some(name) = in_name.take() {
+                    other.name = Some(name.trim().to_owned()).filter(|name| !name.is_empty());
+                } else {
+                    in_transcript = false;
+                },
+                _ => (),
+            },
+            Token::Text(ref s) => {
+                if let Some(ref mut name) = in_name {
+                    name.push_str(s);
+                } else if let (true, Some(transcript)) = (in_transcript, transcript.as_mut()) {
+                    transcript.push_str(s);
+                }
+            }
+        }
+    }
+
+    let timestamp = timestamp.ok_or("missing or invalid timestamp")?;
+    let duration = duration.unwrap_or_default();
+    let key = other.number.clone()
+        .or_else(|| normalize_phone_number(contact))
+        .unwrap_or_else(|| contact.to_owned());
+
+    let call_kind = match kind {
+        "Missed" => CallKind::Missed,
+        "Voicemail" => CallKind::Voicemail {
+            duration,
+            transcript: transcript.map(|t| t.trim().to_owned()).filter(|t| !t.is_empty()),
+        },
+        _ => CallKind::Ended { duration },
+    };
+    let participant = other.participant();
+    let sender = if kind == "Placed" {
+        Person { is_self: true, ..Person::default() }.participant()
+    } else {
+        participant.clone()
+    };
+    let event = Event {
+        id: stem.to_owned(),
+        timestamp,
+        sender,
+        medium: model::Medium::GoogleVoice { self_phone: None },
+        kind: EventKind::Call(Call {
+            kind: call_kind,
+            media_type: Some(raw::MediaType::AudioOnly),
+            participants: vec![participant.clone()],
+        }),
+    };
+    Ok((key, vec![participant], vec![event]))
+}
+
+/// Parse an ISO 8601 date and time with an offset, such as "2017-07-13T22:40:00.000-04:00".
+fn parse_datetime(s: &str) -> Option<Timestamp> {
+    let (date, time) = s.split_once('T')?;
+    let mut date = date.split('-').map(str::parse::<u32>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let zone_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+    let (time, zone) = time.split_at(zone_start);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.split(':').map(str::parse::<u32>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let micros = if fraction.is_empty() {
+        0
+    } else if fraction.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{:0<6}", &fraction[..fraction.len().min(6)]).parse::<i64>().ok()?
+    } else {
+        return None;
+    };
+    let offset = match zone {
+        "" | "Z" => 0,
+        _ => {
+            let (hours, minutes) = zone[1..].split_once(':').unwrap_or((&zone[1..], "0"));
+            let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+            if !(0..=24).contains(&hours) || !(0..60).contains(&minutes) {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if zone.starts_with('-') { -offset } else { offset }
+        }
+    };
+
+    let secs_of_day = hour * 3600 + minute * 60 + second;
+    let utc = Timestamp::from_utc(i64::from(year), month, day, secs_of_day)?;
+    utc.as_micros().checked_add(micros)?.checked_sub(offset * 1_000_000).map(Timestamp::from_micros)
+}
+
+/// Parse an ISO 8601 duration such as "PT1M23S". Fractions of a second are dropped. `None` if it
+/// doesn't fit in a `u64` of seconds.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let mut secs = 0;
+    let mut number = String::new();
+    for c in s.strip_prefix("PT")?.chars() {
+        let unit = match c {
+            '0'..='9' | '.' => {
+                number.push(c);
+                continue;
+            }
+            'H' => 3600,
+            'M' => 60,
+            'S' => 1,
+            _ => return None,
+        };
+        let whole = number.split('.').next().unwrap_or_default();
+        secs = whole.parse::<u64>().ok()?.checked_mul(unit)?.checked_add(secs)?;
+        number.clear();
+    }
+    Some(Duration::from_secs(secs))
+}
+
+/// Just enough of HTML to read the files Takeout produces.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Start { name: String, attrs: Vec<(String, String)> },
+    End { name: String },
+    Text(String),
+}
+
+fn has_class(attrs: &[(String, String)], class: &str) -> bool {
+    attr(attrs, "class").is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = html;
+    while !rest.is_empty() {
+        let lt = rest.find('<').unwrap_or(rest.len());
+        if lt > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..lt])));
+            rest = &rest[lt..];
+            continue;
+        }
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let gt = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[1..gt];
+        rest = rest.get(gt + 1..).unwrap_or_default();
+
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::End { name: name.trim().to_ascii_lowercase() });
+        } else if !tag.starts_with('!') && !tag.starts_with('?') {
+            let tag = tag.trim_end_matches('/');
+            let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+            tokens.push(Token::Start {
+                name: tag[..name_end].to_ascii_lowercase(),
+                attrs: parse_attrs(&tag[name_end..]),
+            });
+        }
+    }
+    tokens
+}
+
+fn parse_attrs(mut s: &str) -> Vec<(String, String)> {
+    let mut attrs = vec![];
+    loop {
+        s = s.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if s.is_empty() {
+            return attrs;
+        }
+        let name_end = s.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(s.len());
+        let name = s[..name_end].to_ascii_lowercase();
+        s = s[name_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after) = s.strip_prefix('=') {
+            let after = after.trim_start();
+            match after.chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => {
+                    let end = after[1..].find(quote).map_or(after.len(), |i| i + 1);
+                    value = &after[1..end];
+                    s = after.get(end + 1..).unwrap_or_default();
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    value = &after[..end];
+                    s = &after[end..];
+                }
+            }
+        }
+        attrs.push((name, decode_entities(value)));
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end + 1];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
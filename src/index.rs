@@ -0,0 +1,259 @@
+//! An index of where each conversation is in Hangouts.json, for reading just one of them.
+//!
+//! Building the index means parsing the whole file once; after that, `Index::conversation` seeks
+//! straight to the conversation asked for and parses only that. The index is saved next to the
+//! file, with `.index` appended to its name, and is rebuilt if the file's size or modification
+//! time changes. It only works on uncompressed files, since compressed ones can't be seeked in.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::compression::Compression;
+use crate::model;
+use crate::raw;
+use crate::stream::{self, ConversationStream, Layout};
+use crate::Error;
+
+/// Bumped whenever the format of the index changes.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Index {
+    #[serde(skip)] source: PathBuf,
+    version: u32,
+    legacy: bool,
+    source_len: u64,
+    source_modified: Option<(u64, u32)>,
+    conversations: Vec<IndexEntry>,
+    /// Positions in `conversations`, by ID.
+    #[serde(skip)] by_id: HashMap<String, usize>,
+}
+
+/// What the index knows about a conversation without reading it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub id: String,
+    pub kind: raw::ConversationType,
+    pub name: Option<String>,
+    /// Participants' names, where known.
+    pub participants: Vec<String>,
+    pub events: usize,
+    /// Where the conversation is in the `conversations` array.
+    pub index: usize,
+    /// Where the conversation's JSON is in the file, in bytes.
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Where the index for the given source file goes: the same path with `.index` appended.
+pub fn index_path(source: impl AsRef<Path>) -> PathBuf {
+    let mut path = OsString::from(source.as_ref());
+    path.push(".index");
+    PathBuf::from(path)
+}
+
+impl Index {
+    /// Index a Hangouts.json file by parsing it all.
+    pub fn build(source: impl AsRef<Path>) -> Result<Index, Error> {
+        let source = source.as_ref();
+        let (source_len, source_modified) = file_metadata(source)?;
+        let mut reader = BufReader::new(File::open(source)?);
+        let compression = Compression::detect(reader.fill_buf()?);
+        if compression != Compression::None {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!(
+                "can't index {} compressed file; decompress it first", compression)).into());
+        }
+
+        let mut stream = ConversationStream::new(Counter { inner: reader, pos: 0 });
+        let mut conversations = vec![];
+        while let Some((index, json)) = stream.next_json()? {
+            let json = json.get();
+            // Leading whitespace is skipped before the JSON is captured, and nothing after it is
+            // read, so it ends where the reader is now.
+            let len = json.len() as u64;
+            let offset = stream.get_ref().pos - len;
+            let convo = stream::parse_conversation(stream.layout(), index, json.as_bytes())?;
+            conversations.push(IndexEntry::new(convo, index, offset, len));
+        }
+
+        let mut index = Index {
+            source: source.to_owned(),
+            version: FORMAT_VERSION,
+            legacy: stream.layout() == Layout::Legacy,
+            source_len,
+            source_modified,
+            conversations,
+            by_id: HashMap::new(),
+        };
+        index.index_ids();
+        Ok(index)
+    }
+
+    /// Load the saved index for a source file, if there is one and the file hasn't changed
+    /// since.
+    pub fn load(source: impl AsRef<Path>) -> Result<Option<Index>, Error> {
+        let source = source.as_ref();
+        let file = match File::open(index_path(source)) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut index: Index = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(index) => index,
+            Err(e) if e.is_io() => return Err(Error::Io(e.into())),
+            Err(_) => return Ok(None),
+        };
+        if index.version != FORMAT_VERSION
+            || (index.source_len, index.source_modified) != file_metadata(source)?
+        {
+            return Ok(None);
+        }
+        index.source = source.to_owned();
+        index.index_ids();
+        Ok(Some(index))
+    }
+
+    /// If an ID appears more than once, the first conversation with it wins.
+    fn index_ids(&mut self) {
+        self.by_id.clear();
+        for (i, entry) in self.conversations.iter().enumerate() {
+            self.by_id.entry(entry.id.clone()).or_insert(i);
+        }
+    }
+
+    /// Save the index next to its source file.
+    pub fn save(&self) -> Result<(), Error> {
+        // Written under another name and then renamed, so an interrupted write leaves no index
+        // rather than a truncated one.
+        let path = index_path(&self.source);
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        let result = self.write_index(Path::new(&temp))
+            .and_then(|()| fs::rename(&temp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        Ok(result?)
+    }
+
+    fn write_index(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, self)?;
+        file.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()
+    }
+
+    /// Load the index for a source file, or if it has none or it's out of date, build one and
+    /// save it for next time.
+    pub fn open(source: impl AsRef<Path>) -> Result<Index, Error> {
+        let source = source.as_ref();
+        if let Some(index) = Index::load(source)? {
+            return Ok(index);
+        }
+        let index = Index::build(source)?;
+        // Like a cache, the index only saves time, so failing to write it isn't an error.
+        let _ = index.save();
+        Ok(index)
+    }
+
+    /// The file this indexes.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Every conversation, in the order they appear in the file.
+    pub fn conversations(&self) -> &[IndexEntry] {
+        &self.conversations
+    }
+
+    pub fn get(&self, id: &str) -> Option<&IndexEntry> {
+        self.by_id.get(id).map(|&i| &self.conversations[i])
+    }
+
+    /// Conversations with a participant of the given name, ignoring case.
+    pub fn with_participant<'a>(&'a self, name: &str)
+        -> impl Iterator<Item = &'a IndexEntry> + 'a
+    {
+        let name = model::normalize_name(name);
+        self.conversations.iter().filter(move |entry| {
+            entry.participants.iter().any(|p| model::normalize_name(p) == name)
+        })
+    }
+
+    pub fn of_kind<'a>(&'a self, kind: &'a raw::ConversationType)
+        -> impl Iterator<Item = &'a IndexEntry> + 'a
+    {
+        self.conversations.iter().filter(move |entry| entry.kind == *kind)
+    }
+
+    /// Read one conversation from the source file.
+    pub fn read(&self, entry: &IndexEntry) -> Result<raw::Conversation, Error> {
+        let mut file = File::open(&self.source)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut json = vec![];
+        file.take(entry.len).read_to_end(&mut json)?;
+        let layout = if self.legacy { Layout::Legacy } else { Layout::Current };
+        stream::parse_conversation(layout, entry.index, &json)
+    }
+
+    /// Read the conversation with the given ID from the source file, if there is one.
+    pub fn conversation(&self, id: &str) -> Result<Option<raw::Conversation>, Error> {
+        self.get(id).map(|entry| self.read(entry)).transpose()
+    }
+}
+
+impl IndexEntry {
+    fn new(convo: raw::Conversation, index: usize, offset: u64, len: u64) -> Self {
+        let details = convo.header.details;
+        IndexEntry {
+            id: convo.header.conversation_id.id,
+            kind: details.typ,
+            name: details.name,
+            participants: details.participant_data.into_iter()
+                .filter_map(|p| p.fallback_name)
+                .collect(),
+            events: convo.events.len(),
+            index,
+            offset,
+            len,
+        }
+    }
+}
+
+/// A file's size, and modification time as seconds and nanoseconds since the Unix epoch if the
+/// platform has them.
+pub(crate) fn file_metadata(path: &Path) -> io::Result<(u64, Option<(u64, u32)>)> {
+    let meta = fs::metadata(path)?;
+    let modified = meta.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| (since.as_secs(), since.subsec_nanos()));
+    Ok((meta.len(), modified))
+}
+
+/// Keeps track of how far into the file has been read.
+struct Counter<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Counter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.pos += amt as u64;
+    }
+}
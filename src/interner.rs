@@ -0,0 +1,174 @@
+//! Compact handles for participant and conversation IDs.
+//!
+//! A `raw::ParticipantId` is two strings, so keying maps by it or copying it around means a lot of
+//! allocation and hashing. An `Interner` gives each distinct ID a small `Copy` handle instead, and
+//! keeps the tables to get the full IDs back. Handles are only meaningful to the interner that
+//! made them.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::BufRead;
+
+use crate::raw;
+use crate::stream::ConversationStream;
+use crate::{Error, Hangouts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParticipantHandle(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConversationHandle(u32);
+
+impl ParticipantHandle {
+    /// Handles are numbered from zero, in the order the IDs were first seen.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl ConversationHandle {
+    /// Handles are numbered from zero, in the order the IDs were first seen.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    participants: Vec<raw::ParticipantId>,
+    participant_handles: HashMap<raw::ParticipantId, ParticipantHandle>,
+    conversations: Vec<raw::ConversationId>,
+    conversation_handles: HashMap<String, ConversationHandle>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The handle for a participant ID, giving it a new one if it hasn't been seen before.
+    pub fn intern_participant(&mut self, id: &raw::ParticipantId) -> ParticipantHandle {
+        if let Some(&handle) = self.participant_handles.get(id) {
+            return handle;
+        }
+        let handle = ParticipantHandle(next_handle(self.participants.len()));
+        self.participants.push(id.clone());
+        self.participant_handles.insert(id.clone(), handle);
+        handle
+    }
+
+    /// The handle for a conversation ID, giving it a new one if it hasn't been seen before.
+    pub fn intern_conversation(&mut self, id: &raw::ConversationId) -> ConversationHandle {
+        if let Some(&handle) = self.conversation_handles.get(&id.id) {
+            return handle;
+        }
+        let handle = ConversationHandle(next_handle(self.conversations.len()));
+        self.conversations.push(id.clone());
+        self.conversation_handles.insert(id.id.clone(), handle);
+        handle
+    }
+
+    /// Intern a conversation's ID and every participant ID mentioned anywhere in it.
+    pub fn intern(&mut self, convo: &raw::Conversation) -> ConversationHandle {
+        let handle = self.intern_conversation(&convo.header.conversation_id);
+        let details = &convo.header.details;
+        for participant in &details.participant_data {
+            self.intern_participant(&participant.id);
+        }
+        for id in &details.current_participant {
+            self.intern_participant(id);
+        }
+        self.intern_participant(&details.self_conversation_state.inviter_id);
+        for state in &details.read_state {
+            self.intern_participant(&state.participant_id);
+        }
+        for event in &convo.events {
+            self.intern_participant(&event.header.sender_id);
+            match event.data {
+                raw::EventData::HangoutEvent { ref participant_id, .. }
+                | raw::EventData::MembershipChange { ref participant_id, .. } => {
+                    for id in participant_id {
+                        self.intern_participant(id);
+                    }
+                }
+                _ => (),
+            }
+        }
+        handle
+    }
+
+    /// The handle for a participant ID, if it has been interned.
+    pub fn participant_handle(&self, id: &raw::ParticipantId) -> Option<ParticipantHandle> {
+        self.participant_handles.get(id).copied()
+    }
+
+    /// The handle for a conversation ID, if it has been interned.
+    pub fn conversation_handle(&self, id: &str) -> Option<ConversationHandle> {
+        self.conversation_handles.get(id).copied()
+    }
+
+    /// The full ID for a handle. Panics if the handle came from a different interner.
+    pub fn participant(&self, handle: ParticipantHandle) -> &raw::ParticipantId {
+        &self.participants[handle.index()]
+    }
+
+    /// The full ID for a handle. Panics if the handle came from a different interner.
+    pub fn conversation(&self, handle: ConversationHandle) -> &raw::ConversationId {
+        &self.conversations[handle.index()]
+    }
+
+    pub fn participants(&self)
+        -> impl ExactSizeIterator<Item = (ParticipantHandle, &raw::ParticipantId)>
+    {
+        self.participants.iter().enumerate().map(|(i, id)| (ParticipantHandle(i as u32), id))
+    }
+
+    pub fn conversations(&self)
+        -> impl ExactSizeIterator<Item = (ConversationHandle, &raw::ConversationId)>
+    {
+        self.conversations.iter().enumerate().map(|(i, id)| (ConversationHandle(i as u32), id))
+    }
+}
+
+fn next_handle(len: usize) -> u32 {
+    u32::try_from(len).expect("more than 2^32 distinct IDs")
+}
+
+impl<R: BufRead> ConversationStream<R> {
+    /// Intern each conversation's IDs as it is parsed, yielding it along with its handle.
+    pub fn interned<'a>(self, interner: &'a mut Interner)
+        -> impl Iterator<Item = Result<(ConversationHandle, raw::Conversation), Error>> + 'a
+    where
+        R: 'a,
+    {
+        self.map(move |convo| {
+            let convo = convo?;
+            Ok((interner.intern(&convo), convo))
+        })
+    }
+}
+
+impl Hangouts {
+    /// Intern the IDs in every conversation.
+    pub fn interner(&self) -> Interner {
+        let mut interner = Interner::new();
+        for convo in &self.conversations {
+            interner.intern(convo);
+        }
+        interner
+    }
+
+    /// Like `participants`, but keyed by handles from the given interner, which this interns the
+    /// participants' IDs into.
+    pub fn interned_participants(&self, interner: &mut Interner)
+        -> HashMap<ParticipantHandle, &raw::ParticipantData>
+    {
+        let mut map = HashMap::new();
+        for convo in &self.conversations {
+            for participant in &convo.header.details.participant_data {
+                map.entry(interner.intern_participant(&participant.id)).or_insert(participant);
+            }
+        }
+        map
+    }
+}
@@ -0,0 +1,323 @@
+//! Support for the "lenient" feature, which keeps data the `raw` types don't know about instead of
+//! rejecting or dropping it.
+//!
+//! Unrecognized fields end up in the `extra` map of the struct (or struct variant) they were found
+//! in, and events, chat segments, and call events of unrecognized types become `Unknown` variants.
+//! `Hangouts::unknown_data` lists everything that was captured this way.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::raw::{self, Extra};
+
+impl TryFrom<Extra> for raw::Event {
+    type Error = serde_json::Error;
+
+    fn try_from(mut fields: Extra) -> Result<Self, Self::Error> {
+        let event_type = match fields.remove("event_type") {
+            Some(v) => serde_json::from_value(v)?,
+            None => return Err(serde::de::Error::missing_field("event_type")),
+        };
+
+        let mut data_fields = Extra::new();
+        for key in raw::EVENT_DATA_KEYS {
+            if let Some(v) = fields.remove(*key) {
+                data_fields.insert((*key).to_owned(), v);
+            }
+        }
+
+        // Anything the header doesn't recognize belongs to the event as a whole.
+        let mut header: raw::EventHeader = serde_json::from_value(Value::Object(fields))?;
+        let mut extra = std::mem::take(&mut header.extra);
+
+        let known = if data_fields.len() == 1 {
+            serde_json::from_value(Value::Object(data_fields.clone())).ok()
+        } else {
+            None
+        };
+        let data = known.unwrap_or_else(|| {
+            extra.append(&mut data_fields);
+            raw::EventData::Unknown(Value::Object(std::mem::take(&mut extra)))
+        });
+
+        Ok(raw::Event { header, data, event_type, extra })
+    }
+}
+
+/// Something that was captured instead of causing a parse failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unknown {
+    /// A field with no place in the `raw` types, found in the object at `path`.
+    Field { path: String, name: String, value: Value },
+
+    /// An event, chat segment, or call event of unrecognized type, found at `path`.
+    Variant { path: String, value: Value },
+}
+
+impl Unknown {
+    pub fn path(&self) -> &str {
+        match self {
+            Unknown::Field { path, .. } | Unknown::Variant { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unknown::Field { path, name, value } => {
+                write!(f, "unknown field {:?} at {}: {}", name, path, value)
+            }
+            Unknown::Variant { path, value } => write!(f, "unknown variant at {}: {}", path, value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UnknownReport {
+    pub entries: Vec<Unknown>,
+}
+
+impl UnknownReport {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add everything captured in one conversation, which is at the given index in the
+    /// `conversations` array. Useful along with `Hangouts::stream_conversations`.
+    pub fn scan_conversation(&mut self, index: usize, convo: &raw::Conversation) {
+        let conversations = Path::Field(&Path::Root, "conversations");
+        convo.walk(&Path::Index(&conversations, index), self);
+    }
+
+    /// Number of occurrences of each kind of unknown data, with array indices removed from the
+    /// paths, e.g. `"conversations[].events[].chat_message.foo" => 12`.
+    pub fn summary(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.entries {
+            let mut key = strip_indices(entry.path());
+            if let Unknown::Field { name, .. } = entry {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+                key += name;
+            }
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    fn extra(&mut self, path: &Path<'_>, extra: &Extra) {
+        for (name, value) in extra {
+            self.entries.push(Unknown::Field {
+                path: path.to_string(),
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    fn variant(&mut self, path: &Path<'_>, value: &Value) {
+        self.entries.push(Unknown::Variant {
+            path: path.to_string(),
+            value: value.clone(),
+        });
+    }
+}
+
+fn strip_indices(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                out.push_str("[]");
+            }
+            ']' => in_index = false,
+            _ if in_index => (),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl raw::Hangouts {
+    pub fn unknown_data(&self) -> UnknownReport {
+        let mut report = UnknownReport::default();
+        report.extra(&Path::Root, &self.extra);
+        for (i, convo) in self.conversations.iter().enumerate() {
+            report.scan_conversation(i, convo);
+        }
+        report
+    }
+}
+
+/// A JSON path, only turned into a string when something is found there.
+enum Path<'a> {
+    Root,
+    Field(&'a Path<'a>, &'static str),
+    Index(&'a Path<'a>, usize),
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Path::Root => Ok(()),
+            Path::Field(Path::Root, name) => f.write_str(name),
+            Path::Field(parent, name) => write!(f, "{}.{}", parent, name),
+            Path::Index(parent, i) => write!(f, "{}[{}]", parent, i),
+        }
+    }
+}
+
+trait Walk {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport);
+}
+
+impl<T: Walk> Walk for Vec<T> {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        for (i, item) in self.iter().enumerate() {
+            item.walk(&Path::Index(path, i), report);
+        }
+    }
+}
+
+impl<T: Walk> Walk for Option<T> {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        if let Some(item) = self {
+            item.walk(path, report);
+        }
+    }
+}
+
+/// Implement `Walk` for a struct with an `extra` field, given its fields which can contain
+/// further unknown data and their names in the JSON.
+macro_rules! walk_struct {
+    ($ty:ident $(, $field:ident => $name:expr)*) => {
+        impl Walk for raw::$ty {
+            fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+                report.extra(path, &self.extra);
+                $( self.$field.walk(&Path::Field(path, $name), report); )*
+            }
+        }
+    }
+}
+
+walk_struct!(Conversation, header => "conversation", events => "events");
+walk_struct!(ConversationHeader, conversation_id => "conversation_id", details => "conversation");
+walk_struct!(ConversationDetails,
+    id => "id",
+    self_conversation_state => "self_conversation_state",
+    read_state => "read_state",
+    current_participant => "current_participant",
+    participant_data => "participant_data");
+walk_struct!(ConversationId);
+walk_struct!(SelfConversationState,
+    self_read_state => "self_read_state",
+    inviter_id => "inviter_id",
+    delivery_medium_option => "delivery_medium_option");
+walk_struct!(DeliveryMediumOption, delivery_medium => "delivery_medium");
+walk_struct!(DeliveryMedium, self_phone => "self_phone");
+walk_struct!(PhoneNumber, i18n_data => "i18n_data");
+walk_struct!(PhoneNumberI18nData);
+walk_struct!(ReadState, participant_id => "participant_id");
+walk_struct!(ParticipantId);
+walk_struct!(ParticipantData, id => "id", phone_number => "phone_number");
+walk_struct!(EventHeader,
+    conversation_id => "conversation_id",
+    sender_id => "sender_id",
+    self_event_state => "self_event_state",
+    delivery_medium => "delivery_medium");
+walk_struct!(SelfEventState, user_id => "user_id");
+walk_struct!(ChatSegments, segments => "segment", attachments => "attachment");
+walk_struct!(Annotation);
+walk_struct!(LinkData);
+walk_struct!(Formatting);
+walk_struct!(AttachmentSegment, embed_item => "embed_item");
+walk_struct!(EmbedItem,
+    plus_photo => "plus_photo",
+    plus_audio_v2 => "plus_audio_v2",
+    place_v2 => "place_v2",
+    thing_v2 => "thing_v2");
+walk_struct!(PlusPhoto, thumbnail => "thumbnail");
+walk_struct!(Thumbnail);
+walk_struct!(PlaceV2,
+    address => "address",
+    geo => "geo",
+    representative_image => "representative_image");
+walk_struct!(PlusAudioV2);
+walk_struct!(Address, postal_address_v2 => "postal_address_v2");
+walk_struct!(PostalAddressV2);
+walk_struct!(Geo, geo_coordinates_v2 => "geo_coordinates_v2");
+walk_struct!(GeoCoordinatesV2);
+walk_struct!(RepresentativeImage, image_object_v2 => "image_object_v2");
+walk_struct!(ImageObjectV2);
+walk_struct!(ThingV2, representative_image => "representative_image");
+
+impl Walk for raw::Event {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        // The header and data are flattened into the event object.
+        report.extra(path, &self.extra);
+        self.header.walk(path, report);
+        self.data.walk(path, report);
+    }
+}
+
+impl Walk for raw::EventData {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        match self {
+            raw::EventData::ChatMessage { message_content, annotation, extra } => {
+                let path = Path::Field(path, "chat_message");
+                report.extra(&path, extra);
+                message_content.walk(&Path::Field(&path, "message_content"), report);
+                annotation.walk(&Path::Field(&path, "annotation"), report);
+            }
+            raw::EventData::ConversationRename { extra, .. } => {
+                report.extra(&Path::Field(path, "conversation_rename"), extra);
+            }
+            raw::EventData::HangoutEvent { data, participant_id, .. } => {
+                let path = Path::Field(path, "hangout_event");
+                data.walk(&path, report);
+                participant_id.walk(&Path::Field(&path, "participant_id"), report);
+            }
+            raw::EventData::MembershipChange { participant_id, extra, .. } => {
+                let path = Path::Field(path, "membership_change");
+                report.extra(&path, extra);
+                participant_id.walk(&Path::Field(&path, "participant_id"), report);
+            }
+            raw::EventData::Unknown(value) => report.variant(path, value),
+        }
+    }
+}
+
+impl Walk for raw::ChatSegment {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        match self {
+            raw::ChatSegment::Text { formatting, extra, .. } => {
+                report.extra(path, extra);
+                formatting.walk(&Path::Field(path, "formatting"), report);
+            }
+            raw::ChatSegment::Link { link_data, formatting, extra, .. } => {
+                report.extra(path, extra);
+                link_data.walk(&Path::Field(path, "link_data"), report);
+                formatting.walk(&Path::Field(path, "formatting"), report);
+            }
+            raw::ChatSegment::LineBreak { extra, .. } => report.extra(path, extra),
+            raw::ChatSegment::Unknown(value) => report.variant(path, value),
+        }
+    }
+}
+
+impl Walk for raw::HangoutEvent {
+    fn walk(&self, path: &Path<'_>, report: &mut UnknownReport) {
+        match self {
+            raw::HangoutEvent::StartHangout { extra }
+                | raw::HangoutEvent::EndHangout { extra, .. } => report.extra(path, extra),
+            raw::HangoutEvent::Unknown(value) => report.variant(path, value),
+        }
+    }
+}
@@ -2,12 +2,47 @@
 
 #[macro_use] extern crate serde_derive;
 
+mod error;
+pub use crate::error::Error;
+
+mod timestamp;
+pub use crate::timestamp::Timestamp;
+
+#[cfg(all(feature = "lenient", feature = "deny_unknown_fields"))]
+compile_error!("the \"lenient\" and \"deny_unknown_fields\" features are mutually exclusive");
+
+#[cfg(feature = "lenient")]
+pub mod lenient;
+
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod compression;
+pub mod export;
+pub mod google_chat;
+pub mod google_voice;
+pub mod index;
+pub mod interner;
+pub mod model;
+#[cfg(feature = "rayon")]
+mod parallel;
 pub mod raw;
+pub mod render;
+pub mod resolver;
+pub mod stream;
+pub mod takeout;
+pub mod text;
 pub use crate::raw::Hangouts;
 
 use std::collections::hash_map::*;
 
 impl Hangouts {
+    /// Parse a whole Hangouts.json. Errors report the path of the offending node within the JSON.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Hangouts, Error> {
+        let mut stream = Hangouts::stream_conversations(reader);
+        let conversations = stream.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok(Hangouts { conversations, extra: stream.into_extra() })
+    }
+
     pub fn participants(&self) -> HashMap<raw::ParticipantId, raw::ParticipantData> {
         let mut map = HashMap::new();
 
@@ -23,15 +58,8 @@ impl Hangouts {
     }
 }
 
-impl raw::EventHeader {
-    pub fn timestamp(&self) -> Result<(i64, u32), std::num::ParseIntError> {
-        let usecs: u32 = self.timestamp[self.timestamp.len() - 6..].parse()?;
-        let secs: i64 = self.timestamp[0..(self.timestamp.len() - 6)].parse()?;
-        Ok((secs, usecs * 1_000))
-    }
-}
-
 impl raw::Event {
+    /// The text of a chat message, leaving out links. `text` has more options.
     pub fn text_only(&self) -> Option<String> {
         let msg = match self.data {
             raw::EventData::ChatMessage { ref message_content, .. } => message_content,
@@ -44,17 +72,17 @@ impl raw::Event {
 
         for segment in &msg.segments {
             match segment {
-                raw::ChatSegment::Text { ref text, formatting: _ } => {
+                raw::ChatSegment::Text { ref text, .. } => {
                     combined += text;
                 }
-                raw::ChatSegment::LineBreak { ref text } => {
+                raw::ChatSegment::LineBreak { ref text, .. } => {
                     if let Some(text) = text {
                         combined += text;
                     } else {
                         combined.push('\n');
                     }
                 }
-                raw::ChatSegment::Link { .. } => (),
+                raw::ChatSegment::Link { .. } | raw::ChatSegment::Unknown(_) => (),
             }
         }
 
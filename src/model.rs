@@ -0,0 +1,654 @@
+//! A high-level view of a Hangouts archive.
+//!
+//! Where the types in `raw` mirror the JSON as closely as possible, these resolve participant
+//! names, parse timestamps, sort events chronologically, and replace the various stringly-typed
+//! fields with enums.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::raw;
+use crate::{Error, Timestamp};
+
+#[derive(Debug)]
+pub struct Archive {
+    pub conversations: Vec<Conversation>,
+    pub participants: HashMap<raw::ParticipantId, Participant>,
+    /// Things in the data that didn't make sense, but which weren't bad enough to fail over, such
+    /// as attachments and calls that were kept as `Unknown` because of them.
+    pub problems: Vec<Error>,
+}
+
+impl Archive {
+    pub fn conversation(&self, id: &str) -> Option<&Conversation> {
+        self.conversations.iter().find(|convo| convo.id == id)
+    }
+
+    pub fn participant(&self, id: &raw::ParticipantId) -> Option<&Participant> {
+        self.participants.get(id)
+    }
+}
+
+impl TryFrom<raw::Hangouts> for Archive {
+    type Error = Error;
+
+    fn try_from(hangouts: raw::Hangouts) -> Result<Self, Self::Error> {
+        let participants: HashMap<raw::ParticipantId, Participant> = hangouts.participants()
+            .into_iter()
+            .map(|(id, data)| (id, Participant::from(data)))
+            .collect();
+
+        let mut problems = vec![];
+        let conversations = hangouts.conversations
+            .into_iter()
+            .map(|convo| Conversation::from_raw(convo, &participants, &mut problems))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Archive { conversations, participants, problems })
+    }
+}
+
+#[derive(Debug)]
+pub struct Conversation {
+    pub id: String,
+    pub kind: raw::ConversationType,
+    pub name: Option<String>,
+    pub participants: Vec<Participant>,
+    /// Sorted by timestamp, oldest first.
+    pub events: Vec<Event>,
+}
+
+impl Conversation {
+    fn from_raw(
+        convo: raw::Conversation,
+        directory: &HashMap<raw::ParticipantId, Participant>,
+        problems: &mut Vec<Error>,
+    ) -> Result<Self, Error> {
+        let details = convo.header.details;
+
+        let participants = details.participant_data
+            .into_iter()
+            .map(|p| resolve(&p.id, directory))
+            .collect();
+
+        let mut events = convo.events
+            .into_iter()
+            .map(|event| Event::from_raw(event, directory, problems))
+            .collect::<Result<Vec<_>, _>>()?;
+        events.sort_by_key(|event| event.timestamp);
+
+        Ok(Conversation {
+            id: convo.header.conversation_id.id,
+            kind: details.typ,
+            name: details.name,
+            participants,
+            events,
+        })
+    }
+
+    /// The conversation's name if it has one, otherwise the names of its participants.
+    pub fn display_name(&self) -> String {
+        if let Some(ref name) = self.name {
+            return name.clone();
+        }
+        self.participants
+            .iter()
+            .map(Participant::display_name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = (&Event, &Message)> {
+        self.events.iter().filter_map(|event| match event.kind {
+            EventKind::Message(ref msg) => Some((event, msg)),
+            _ => None,
+        })
+    }
+
+    /// Whether the two conversations have any (non-empty) messages in common.
+    pub(crate) fn shares_history(&self, other: &Conversation) -> bool {
+        let keys = self.events.iter()
+            .filter_map(message_key)
+            .filter(|(_, text)| !text.is_empty())
+            .collect::<HashSet<_>>();
+        other.events.iter()
+            .filter_map(message_key)
+            .any(|key| keys.contains(&key))
+    }
+
+    /// Add the events of another copy of this conversation, from a different export, skipping
+    /// messages this one already has. Senders are matched up with this conversation's
+    /// participants by phone number or name.
+    pub(crate) fn absorb(&mut self, other: Conversation) {
+        let seen = self.events.iter()
+            .filter_map(message_key)
+            .collect::<HashSet<_>>();
+
+        for mut event in other.events {
+            if message_key(&event).is_some_and(|key| seen.contains(&key)) {
+                continue;
+            }
+            if let Some(participant) = self.find_participant(&event.sender) {
+                event.sender = participant.clone();
+            }
+            self.events.push(event);
+        }
+        self.events.sort_by_key(|event| event.timestamp);
+    }
+
+    fn find_participant(&self, other: &Participant) -> Option<&Participant> {
+        let by_phone = other.phone_number.as_ref().and_then(|number| {
+            self.participants.iter().find(|p| p.phone_number.as_ref() == Some(number))
+        });
+        by_phone.or_else(|| {
+            let name = normalize_name(other.name.as_deref()?);
+            self.participants.iter()
+                .find(|p| p.name.as_deref().map(normalize_name).as_ref() == Some(&name))
+        })
+    }
+}
+
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Identifies the same message in different exports, some of which only have timestamps to the
+/// second.
+fn message_key(event: &Event) -> Option<(i64, String)> {
+    match event.kind {
+        EventKind::Message(ref msg) => Some((event.timestamp.parts().0, msg.text().trim().to_owned())),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Participant {
+    pub id: raw::ParticipantId,
+    pub name: Option<String>,
+    /// In E.164 format, e.g. "+15551234567". Only known for people reached by SMS.
+    pub phone_number: Option<String>,
+}
+
+impl Participant {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("[unknown]")
+    }
+}
+
+impl From<raw::ParticipantData> for Participant {
+    fn from(data: raw::ParticipantData) -> Self {
+        Participant {
+            id: data.id,
+            name: data.fallback_name,
+            phone_number: data.phone_number.map(|phone| phone.e164),
+        }
+    }
+}
+
+fn resolve(id: &raw::ParticipantId, directory: &HashMap<raw::ParticipantId, Participant>)
+    -> Participant
+{
+    directory.get(id)
+        .cloned()
+        .unwrap_or_else(|| Participant { id: id.clone(), name: None, phone_number: None })
+}
+
+#[derive(Debug)]
+pub struct Event {
+    pub id: String,
+    pub timestamp: Timestamp,
+    pub sender: Participant,
+    pub medium: Medium,
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Whether this was sent as an SMS or MMS through Google Voice, rather than over Hangouts.
+    pub fn is_sms(&self) -> bool {
+        matches!(self.medium, Medium::GoogleVoice { .. })
+    }
+
+    fn from_raw(
+        event: raw::Event,
+        directory: &HashMap<raw::ParticipantId, Participant>,
+        problems: &mut Vec<Error>,
+    ) -> Result<Self, Error> {
+        let header = event.header;
+        let sender = resolve(&header.sender_id, directory);
+        let participants = |ids: Vec<raw::ParticipantId>| -> Vec<Participant> {
+            ids.iter().map(|id| resolve(id, directory)).collect()
+        };
+
+        let kind = match event.data {
+            raw::EventData::ChatMessage { message_content, annotation, .. } => {
+                let attachments = message_content.attachments
+                    .into_iter()
+                    .map(|a| {
+                        let (attachment, problem) = Attachment::from_embed_item(a.embed_item);
+                        if let Some(Error::Validation { message, .. }) = problem {
+                            problems.push(Error::Validation {
+                                conversation_id: Some(header.conversation_id.id.clone()),
+                                message: format!("event {}: {}", header.event_id, message),
+                            });
+                        }
+                        attachment
+                    })
+                    .collect();
+                EventKind::Message(Message {
+                    segments: message_content.segments,
+                    attachments,
+                    annotations: annotation.unwrap_or_default(),
+                })
+            }
+            raw::EventData::HangoutEvent { data, media_type, participant_id } => {
+                let kind = match data {
+                    raw::HangoutEvent::StartHangout { .. } => CallKind::Started,
+                    raw::HangoutEvent::EndHangout { hangout_duration_secs, .. } => {
+                        match hangout_duration_secs.parse() {
+                            Ok(secs) => CallKind::Ended { duration: Duration::from_secs(secs) },
+                            Err(_) => {
+                                problems.push(Error::Validation {
+                                    conversation_id: Some(header.conversation_id.id.clone()),
+                                    message: format!("event {}: invalid call duration {:?}",
+                                        header.event_id, hangout_duration_secs),
+                                });
+                                CallKind::Unknown(serde_json::Value::String(
+                                    hangout_duration_secs))
+                            }
+                        }
+                    }
+                    raw::HangoutEvent::Unknown(value) => CallKind::Unknown(value),
+                };
+                EventKind::Call(Call {
+                    kind,
+                    media_type,
+                    participants: participants(participant_id),
+                })
+            }
+            raw::EventData::ConversationRename { old_name, new_name, .. } => {
+                EventKind::System(SystemEvent::Rename { old_name, new_name })
+            }
+            raw::EventData::MembershipChange { typ, participant_id, .. } => {
+                EventKind::System(SystemEvent::MembershipChange {
+                    kind: typ,
+                    participants: participants(participant_id),
+                })
+            }
+            raw::EventData::Unknown(value) => EventKind::Unknown(value),
+        };
+
+        Ok(Event {
+            id: header.event_id,
+            timestamp: header.timestamp,
+            sender,
+            medium: Medium::from(header.delivery_medium),
+            kind,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Medium {
+    Hangouts,
+    /// Through Google Voice, from your own number if it's known.
+    GoogleVoice { self_phone: Option<String> },
+    /// Through Google Chat, after the migration.
+    GoogleChat,
+    Unknown(raw::MediumType),
+}
+
+impl From<raw::DeliveryMedium> for Medium {
+    fn from(medium: raw::DeliveryMedium) -> Self {
+        match medium.medium_type {
+            raw::MediumType::Babel => Medium::Hangouts,
+            raw::MediumType::GoogleVoice => Medium::GoogleVoice {
+                self_phone: medium.self_phone.map(|phone| phone.e164),
+            },
+            other => Medium::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EventKind {
+    Message(Message),
+    Call(Call),
+    System(SystemEvent),
+    /// An event of a type this library doesn't recognize (see the "lenient" feature).
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug)]
+pub struct Message {
+    pub segments: Vec<raw::ChatSegment>,
+    pub attachments: Vec<Attachment>,
+    pub annotations: Vec<raw::Annotation>,
+}
+
+impl Message {
+    /// A message of plain text, split into segments the way Hangouts does, with line breaks
+    /// separate.
+    pub(crate) fn from_text(text: &str, attachments: Vec<Attachment>) -> Self {
+        let mut segments = vec![];
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                segments.push(raw::ChatSegment::LineBreak {
+                    text: Some("\n".to_owned()),
+                    extra: raw::Extra::new(),
+                });
+            }
+            if !line.is_empty() {
+                segments.push(raw::ChatSegment::Text {
+                    text: line.to_owned(),
+                    formatting: raw::Formatting::default(),
+                    extra: raw::Extra::new(),
+                });
+            }
+        }
+        Message { segments, attachments, annotations: vec![] }
+    }
+
+    /// Whether this is a `/me` message, whose text is something the sender did.
+    pub fn is_action(&self) -> bool {
+        is_action(&self.annotations)
+    }
+
+    /// The text of the message, with links reduced to their text and line breaks to newlines.
+    pub fn text(&self) -> String {
+        let mut combined = String::new();
+        for segment in &self.segments {
+            match segment {
+                raw::ChatSegment::Text { ref text, .. }
+                    | raw::ChatSegment::Link { ref text, .. } => combined += text,
+                raw::ChatSegment::LineBreak { ref text, .. } => {
+                    combined += text.as_deref().unwrap_or("\n");
+                }
+                raw::ChatSegment::Unknown(_) => (),
+            }
+        }
+        combined
+    }
+}
+
+pub(crate) fn is_action(annotations: &[raw::Annotation]) -> bool {
+    annotations.iter().any(|a| a.typ == raw::AnnotationKind::Action)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Attachment {
+    Photo(Photo),
+    /// Videos are stored the same way as photos, with only the media type differing.
+    Video(Photo),
+    Audio(Audio),
+    Place(Place),
+    Thing(Thing),
+    /// A file sent over Google Chat or MMS, exported alongside the conversation.
+    File(UploadedFile),
+    /// An embed item that has none of the fields this library knows about, or whose fields don't
+    /// make sense.
+    Unknown(Box<raw::EmbedItem>),
+}
+
+impl Attachment {
+    /// Like `Attachment::try_from`, except that an item which doesn't make sense is kept as
+    /// `Attachment::Unknown`, and the reason is returned along with it.
+    pub fn from_embed_item(item: raw::EmbedItem) -> (Attachment, Option<Error>) {
+        match validate_embed_item(&item) {
+            Ok(()) => (attachment_from_valid(item), None),
+            Err(e) => (Attachment::Unknown(Box::new(item)), Some(e)),
+        }
+    }
+
+    /// Width and height of the attachment, or of its preview image, in pixels.
+    pub fn dimensions(&self) -> Option<(u64, u64)> {
+        match self {
+            Attachment::Photo(photo) | Attachment::Video(photo) => photo.thumbnail.dimensions(),
+            Attachment::Place(Place { image, .. }) | Attachment::Thing(Thing { image, .. }) => {
+                image.dimensions()
+            }
+            Attachment::Audio(_) | Attachment::File(_) | Attachment::Unknown(_) => None,
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Attachment::Audio(audio) => Some(audio.duration),
+            _ => None,
+        }
+    }
+
+    /// The kind of media as Takeout names it: "PHOTO", "VIDEO", "AUDIO", etc.
+    pub fn media_type(&self) -> &str {
+        match self {
+            Attachment::Photo(photo) | Attachment::Video(photo) => photo.media_type.as_str(),
+            Attachment::Audio(_) => "AUDIO",
+            Attachment::Place(_) => "PLACE",
+            Attachment::Thing(_) => "THING",
+            Attachment::File(_) => "FILE",
+            Attachment::Unknown(item) => item.types.first().map(String::as_str).unwrap_or("UNKNOWN"),
+        }
+    }
+
+    /// Where the attachment can be viewed online.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Attachment::Photo(photo) | Attachment::Video(photo) => Some(&photo.url),
+            Attachment::Audio(audio) => Some(&audio.url),
+            Attachment::Place(place) => Some(&place.url),
+            Attachment::Thing(thing) => Some(&thing.url),
+            Attachment::File(_) | Attachment::Unknown(_) => None,
+        }
+    }
+}
+
+/// Checks that the item's `type` list agrees with which of its fields are populated, and that the
+/// fields can be parsed.
+impl TryFrom<raw::EmbedItem> for Attachment {
+    type Error = Error;
+
+    fn try_from(item: raw::EmbedItem) -> Result<Self, Error> {
+        validate_embed_item(&item)?;
+        Ok(attachment_from_valid(item))
+    }
+}
+
+fn validate_embed_item(item: &raw::EmbedItem) -> Result<(), Error> {
+    let has_type = |t: &str| item.types.iter().any(|typ| typ == t);
+    // (type, whether its field is populated, whether the type is accounted for)
+    let checks = [
+        ("PLUS_PHOTO", item.plus_photo.is_some(), item.plus_photo.is_some()),
+        ("PLUS_AUDIO_V2", item.plus_audio_v2.is_some(), item.plus_audio_v2.is_some()),
+        ("PLACE_V2", item.place_v2.is_some(), item.place_v2.is_some()),
+        // Places are usually also listed as things, but only have the place field.
+        ("THING_V2", item.thing_v2.is_some(), item.thing_v2.is_some() || item.place_v2.is_some()),
+    ];
+    for &(typ, populated, satisfied) in &checks {
+        let problem = if populated && !has_type(typ) {
+            "has an unexpected"
+        } else if has_type(typ) && !satisfied {
+            "is missing the"
+        } else {
+            continue;
+        };
+        return Err(Error::Validation {
+            conversation_id: None,
+            message: format!("embed item with types {:?} {} {} field",
+                item.types, problem, typ.to_lowercase()),
+        });
+    }
+
+    if let Some(ref audio) = item.plus_audio_v2 {
+        if audio.duration.parse::<u64>().is_err() {
+            return Err(Error::Validation {
+                conversation_id: None,
+                message: format!("invalid audio duration {:?} in embed item", audio.duration),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The conversion itself, for an item that's passed `validate_embed_item`.
+fn attachment_from_valid(item: raw::EmbedItem) -> Attachment {
+    match item {
+        raw::EmbedItem { plus_photo: Some(photo), .. } => {
+            let video = photo.media_type == raw::PhotoMediaType::Video;
+            let photo = Photo {
+                photo_id: photo.photo_id,
+                album_id: photo.album_id,
+                media_type: photo.media_type,
+                url: photo.url,
+                original_content_url: photo.original_content_url,
+                download_url: photo.download_url,
+                thumbnail: Image {
+                    url: photo.thumbnail.image_url,
+                    width: Some(photo.thumbnail.width_px),
+                    height: Some(photo.thumbnail.height_px),
+                },
+            };
+            if video { Attachment::Video(photo) } else { Attachment::Photo(photo) }
+        }
+        raw::EmbedItem { plus_audio_v2: Some(audio), .. } => {
+            // Checked by `validate_embed_item`.
+            let secs = audio.duration.parse().unwrap_or_default();
+            Attachment::Audio(Audio {
+                photo_id: audio.photo_id,
+                album_id: audio.album_id,
+                url: audio.url,
+                embed_url: audio.embed_url,
+                duration: Duration::from_secs(secs),
+            })
+        }
+        raw::EmbedItem { place_v2: Some(place), .. } => {
+            let postal = &place.address.postal_address_v2;
+            let address = [
+                &postal.street_address,
+                &postal.address_locality,
+                &postal.address_region,
+                &postal.postal_code,
+                &postal.address_country,
+            ].iter()
+                .filter_map(|part| part.as_deref())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Attachment::Place(Place {
+                url: place.url,
+                name: place.name,
+                address: if address.is_empty() { None } else { Some(address) },
+                latitude: place.geo.geo_coordinates_v2.latitude,
+                longitude: place.geo.geo_coordinates_v2.longitude,
+                image: Image::from(place.representative_image),
+            })
+        }
+        raw::EmbedItem { thing_v2: Some(thing), .. } => Attachment::Thing(Thing {
+            url: thing.url,
+            name: thing.name,
+            image: Image::from(thing.representative_image),
+        }),
+        item => Attachment::Unknown(Box::new(item)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Photo {
+    pub photo_id: String,
+    pub album_id: String,
+    pub media_type: raw::PhotoMediaType,
+    pub url: String,
+    pub original_content_url: Option<String>,
+    pub download_url: Option<String>,
+    pub thumbnail: Image,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub photo_id: String,
+    pub album_id: String,
+    pub url: String,
+    pub embed_url: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub url: String,
+    pub name: Option<String>,
+    /// The postal address, formatted on one line.
+    pub address: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thing {
+    pub url: String,
+    pub name: Option<String>,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedFile {
+    /// The name of the file when it was uploaded.
+    pub original_name: String,
+    /// The name of the file in the export, in the same folder as the conversation.
+    pub export_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub url: String,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+}
+
+impl Image {
+    pub fn dimensions(&self) -> Option<(u64, u64)> {
+        Some((self.width?, self.height?))
+    }
+}
+
+impl From<raw::RepresentativeImage> for Image {
+    fn from(image: raw::RepresentativeImage) -> Self {
+        let image = image.image_object_v2;
+        Image {
+            url: image.url,
+            width: image.width.and_then(|w| w.parse().ok()),
+            height: image.height.and_then(|h| h.parse().ok()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Call {
+    pub kind: CallKind,
+    pub media_type: Option<raw::MediaType>,
+    pub participants: Vec<Participant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallKind {
+    Started,
+    Ended { duration: Duration },
+    /// A call that wasn't answered.
+    Missed,
+    /// A call that went to voicemail, with the message's transcript if there is one.
+    Voicemail { duration: Duration, transcript: Option<String> },
+    /// A call event of a type this library doesn't recognize (see the "lenient" feature), or the
+    /// duration of an ended call that couldn't be parsed.
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug)]
+pub enum SystemEvent {
+    Rename {
+        old_name: String,
+        new_name: String,
+    },
+    MembershipChange {
+        kind: raw::MembershipChangeType,
+        participants: Vec<Participant>,
+    },
+}
@@ -0,0 +1,34 @@
+//! Parsing conversations on all CPU cores, with the "rayon" feature.
+
+use std::io::Read;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::compression;
+use crate::stream;
+use crate::{Error, Hangouts};
+
+impl Hangouts {
+    /// Parse a whole Hangouts.json held in memory, using all CPU cores. The conversations are
+    /// found with a quick scan of the JSON's structure, and then deserialized in parallel.
+    ///
+    /// The result is the same as from `from_reader`, conversations in the same order, except that
+    /// if more than one conversation fails to parse, which one's error is returned is arbitrary.
+    pub fn from_slice_parallel(json: &[u8]) -> Result<Hangouts, Error> {
+        let (layout, ranges, extra) = stream::scan(json)?;
+        let conversations = ranges.into_par_iter()
+            .enumerate()
+            .map(|(index, range)| stream::parse_conversation(layout, index, &json[range]))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Hangouts { conversations, extra })
+    }
+
+    /// Read a whole Hangouts.json file, which may be compressed, into memory and parse it with
+    /// `from_slice_parallel`.
+    pub fn from_path_parallel(path: impl AsRef<Path>) -> Result<Hangouts, Error> {
+        let mut json = vec![];
+        compression::open(path)?.read_to_end(&mut json)?;
+        Hangouts::from_slice_parallel(&json)
+    }
+}
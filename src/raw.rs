@@ -1,340 +1,762 @@
-#[derive(Deserialize, Debug)]
+use crate::Timestamp;
+
+pub mod borrowed;
+pub mod legacy;
+
+/// Fields that weren't recognized. Only ever populated with the "lenient" feature enabled.
+pub type Extra = serde_json::Map<String, serde_json::Value>;
+
+/// Defines an enum for a field that holds one of a set of known strings, with an `Other` variant
+/// for any values not (yet) known.
+macro_rules! string_enum {
+    ($(#[$attr:meta])* pub enum $name:ident { $($variant:ident = $value:expr,)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Other(s) => s,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(s: String) -> Self {
+                match s.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => $name::Other(s),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        /// Only allocates for `Other` values.
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct EnumVisitor;
+
+                impl serde::de::Visitor<'_> for EnumVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        f.write_str("a string")
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<$name, E> {
+                        Ok(match s {
+                            $($value => $name::$variant,)*
+                            _ => $name::Other(s.to_owned()),
+                        })
+                    }
+
+                    fn visit_string<E: serde::de::Error>(self, s: String) -> Result<$name, E> {
+                        Ok($name::from(s))
+                    }
+                }
+
+                deserializer.deserialize_str(EnumVisitor)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Hangouts {
     pub conversations: Vec<Conversation>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Conversation {
     #[serde(rename="conversation")] pub header: ConversationHeader,
     pub events: Vec<Event>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ConversationHeader {
     pub conversation_id: ConversationId,
     #[serde(rename="conversation")] pub details: ConversationDetails,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ConversationDetails {
     pub id: ConversationId,
-    #[serde(rename="type")] pub typ: String,
-    pub name: Option<String>, // set for type="GROUP" only
+    #[serde(rename="type")] pub typ: ConversationType,
+    #[serde(skip_serializing_if="Option::is_none")] pub name: Option<String>, // set for type="GROUP" only
     pub self_conversation_state: SelfConversationState,
     pub read_state: Vec<ReadState>,
     pub has_active_hangout: bool,
-    pub otr_status: String,
-    pub otr_toggle: String,
+    pub otr_status: OtrStatus,
+    pub otr_toggle: OtrToggle,
     pub current_participant: Vec<ParticipantId>,
     pub participant_data: Vec<ParticipantData>,
     pub fork_on_external_invite: bool,
-    pub network_type: Vec<String>,
-    pub force_history_state: String,
-    pub conversation_ttl_days: Option<String>,
-    pub group_link_sharing_status: String
+    pub network_type: Vec<NetworkType>,
+    pub force_history_state: ForceHistoryState,
+    #[serde(skip_serializing_if="Option::is_none")] pub conversation_ttl_days: Option<String>,
+    pub group_link_sharing_status: GroupLinkSharingStatus,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+string_enum! {
+    pub enum ConversationType {
+        StickyOneToOne = "STICKY_ONE_TO_ONE",
+        Group = "GROUP",
+    }
+}
+
+string_enum! {
+    pub enum OtrStatus {
+        OnTheRecord = "ON_THE_RECORD",
+        OffTheRecord = "OFF_THE_RECORD",
+    }
+}
+
+string_enum! {
+    /// Whether participants can change the conversation's `OtrStatus`.
+    pub enum OtrToggle {
+        Enabled = "ENABLED",
+        Disabled = "DISABLED",
+    }
+}
+
+string_enum! {
+    pub enum NetworkType {
+        Babel = "BABEL",
+        Phone = "PHONE",
+    }
+}
+
+string_enum! {
+    pub enum ForceHistoryState {
+        NoForce = "NO_FORCE",
+        ForceOn = "FORCE_ON",
+        ForceOff = "FORCE_OFF",
+    }
+}
+
+string_enum! {
+    pub enum GroupLinkSharingStatus {
+        On = "LINK_SHARING_ON",
+        Off = "LINK_SHARING_OFF",
+    }
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ConversationId {
     pub id: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct SelfConversationState {
     pub self_read_state: ReadState,
-    pub status: String,
+    pub status: ConversationStatus,
     pub notification_level: NotificationLevel,
-    pub view: Vec<String>,
+    pub view: Vec<ConversationView>,
     pub inviter_id: ParticipantId,
-    pub invite_timestamp: String,
-    pub invitation_display_type: Option<String>,
-    pub invite_affinity: Option<String>,
-    pub sort_timestamp: String,
-    pub active_timestamp: Option<String>,
-    pub delivery_medium_option: Option<serde_json::Value>, // TODO
-    pub is_guest: Option<bool>,
+    pub invite_timestamp: Timestamp,
+    #[serde(skip_serializing_if="Option::is_none")] pub invitation_display_type: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub invite_affinity: Option<String>,
+    pub sort_timestamp: Timestamp,
+    #[serde(skip_serializing_if="Option::is_none")] pub active_timestamp: Option<Timestamp>,
+    #[serde(skip_serializing_if="Option::is_none")] pub delivery_medium_option: Option<Vec<DeliveryMediumOption>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_guest: Option<bool>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+/// One of the ways messages can be sent in a conversation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct DeliveryMediumOption {
+    pub delivery_medium: DeliveryMedium,
+    /// Whether this is the one new messages are sent with.
+    #[serde(skip_serializing_if="Option::is_none")] pub current_default: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub primary: Option<bool>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+string_enum! {
+    pub enum ConversationStatus {
+        Active = "ACTIVE",
+        Invited = "INVITED",
+        Left = "LEFT",
+    }
 }
 
-#[derive(Deserialize, Debug)]
+string_enum! {
+    pub enum ConversationView {
+        Inbox = "INBOX_VIEW",
+        Archived = "ARCHIVED_VIEW",
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ReadState {
     pub participant_id: ParticipantId,
-    pub latest_read_timestamp: String,
+    pub latest_read_timestamp: Timestamp,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ParticipantId {
     pub gaia_id: String,
     pub chat_id: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ParticipantData {
     pub id: ParticipantId,
-    pub fallback_name: Option<String>,
-    pub invitation_status: Option<String>,
-    pub participant_type: Option<String>,
-    pub new_invitation_status: Option<String>,
-    pub in_different_customer_as_requester: Option<bool>,
-    pub is_anonymous_phone: Option<bool>,
-    pub domain_id: Option<String>,
-    pub phone_number: Option<serde_json::Value>, // TODO
+    #[serde(skip_serializing_if="Option::is_none")] pub fallback_name: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub invitation_status: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub participant_type: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub new_invitation_status: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub in_different_customer_as_requester: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_anonymous_phone: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub domain_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub phone_number: Option<PhoneNumber>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PhoneNumber {
+    /// The number in E.164 format, e.g. "+15551234567".
+    pub e164: String,
+    #[serde(skip_serializing_if="Option::is_none")] pub i18n_data: Option<PhoneNumberI18nData>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+/// The number formatted for display, and what libphonenumber made of it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PhoneNumberI18nData {
+    #[serde(skip_serializing_if="Option::is_none")] pub national_number: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub international_number: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub country_code: Option<u32>,
+    #[serde(skip_serializing_if="Option::is_none")] pub region_code: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_valid: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub validation_result: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "lenient", serde(try_from = "Extra"))]
 pub struct Event {
     #[serde(flatten)] pub header: EventHeader,
-    #[serde(flatten)] pub data: EventData,
-    pub event_type: String,
+    #[serde(flatten, serialize_with="EventData::serialize_flattened")] pub data: EventData,
+    pub event_type: EventType,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+string_enum! {
+    pub enum EventType {
+        RegularChatMessage = "REGULAR_CHAT_MESSAGE",
+        Sms = "SMS",
+        Mms = "MMS",
+        Voicemail = "VOICEMAIL",
+        AddUser = "ADD_USER",
+        RemoveUser = "REMOVE_USER",
+        RenameConversation = "RENAME_CONVERSATION",
+        HangoutEvent = "HANGOUT_EVENT",
+        PhoneCall = "PHONE_CALL",
+        OtrModification = "OTR_MODIFICATION",
+        PlanMutation = "PLAN_MUTATION",
+        GroupLinkSharingModification = "GROUP_LINK_SHARING_MODIFICATION",
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct EventHeader {
     pub conversation_id: ConversationId,
     pub sender_id: ParticipantId,
-    pub timestamp: String,
+    pub timestamp: Timestamp,
     pub self_event_state: SelfEventState,
     pub event_id: String,
     pub advances_sort_timestamp: bool,
-    pub event_otr: String,
-    pub delivery_medium: serde_json::Value, // TODO
+    pub event_otr: OtrStatus,
+    pub delivery_medium: DeliveryMedium,
     pub event_version: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+/// How an event was sent: over Hangouts itself, or as an SMS through Google Voice.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct DeliveryMedium {
+    pub medium_type: MediumType,
+    /// Your own Google Voice number, for `GOOGLE_VOICE_MEDIUM`.
+    #[serde(skip_serializing_if="Option::is_none")] pub self_phone: Option<PhoneNumber>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+impl DeliveryMedium {
+    pub fn is_google_voice(&self) -> bool {
+        self.medium_type == MediumType::GoogleVoice
+    }
+}
+
+string_enum! {
+    pub enum MediumType {
+        Unknown = "UNKNOWN_MEDIUM",
+        Babel = "BABEL_MEDIUM",
+        GoogleVoice = "GOOGLE_VOICE_MEDIUM",
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct SelfEventState {
     pub user_id: ParticipantId,
-    pub client_generated_id: Option<String>,
-    pub notification_level: Option<NotificationLevel>,
+    #[serde(skip_serializing_if="Option::is_none")] pub client_generated_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub notification_level: Option<NotificationLevel>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub enum NotificationLevel {
     #[serde(rename="QUIET")] Quiet,
     #[serde(rename="RING")] Ring,
 }
 
-#[derive(Deserialize, Debug)]
+/// Keys under which `EventData` variants appear, flattened into the event object.
+pub(crate) const EVENT_DATA_KEYS: &[&str] = &[
+    "chat_message", "conversation_rename", "hangout_event", "membership_change",
+];
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 //#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub enum EventData {
     #[serde(rename="chat_message")]
     ChatMessage {
         message_content: ChatSegments,
-        annotation: Option<Vec<Annotation>>,
+        #[serde(skip_serializing_if="Option::is_none")] annotation: Option<Vec<Annotation>>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
 
     #[serde(rename="conversation_rename")]
     ConversationRename {
         old_name: String,
         new_name: String,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
 
     #[serde(rename="hangout_event")]
     HangoutEvent {
         #[serde(flatten)] data: HangoutEvent,
-        media_type: Option<String>,
-        #[serde(default)] participant_id: Vec<ParticipantId>,
+        #[serde(skip_serializing_if="Option::is_none")] media_type: Option<MediaType>,
+        #[serde(default, skip_serializing_if="Vec::is_empty")] participant_id: Vec<ParticipantId>,
     },
 
     #[serde(rename="membership_change")]
     MembershipChange {
-        #[serde(rename="type")] typ: String,
+        #[serde(rename="type")] typ: MembershipChangeType,
         participant_id: Vec<ParticipantId>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    /// An event of a type not listed above. With the "lenient" feature, this holds all of the
+    /// event's fields other than the ones in `EventHeader`.
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+
+string_enum! {
+    pub enum MediaType {
+        AudioOnly = "AUDIO_ONLY",
+        AudioVideo = "AUDIO_VIDEO",
+    }
+}
+
+string_enum! {
+    pub enum MembershipChangeType {
+        Join = "JOIN",
+        Leave = "LEAVE",
+    }
+}
+
+impl EventData {
+    /// `Unknown` holds the event's fields as-is, rather than under a key like the other variants.
+    /// It can't be `#[serde(untagged)]` because that breaks deserializing the flattened enum.
+    fn serialize_flattened<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EventData::Unknown(value) => serde::Serialize::serialize(value, serializer),
+            _ => serde::Serialize::serialize(self, serializer),
+        }
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ChatSegments {
-    #[serde(default, rename="segment")] pub segments: Vec<ChatSegment>,
-    #[serde(default, rename="attachment")] pub attachments: Vec<AttachmentSegment>,
+    #[serde(default, rename="segment", skip_serializing_if="Vec::is_empty")] pub segments: Vec<ChatSegment>,
+    #[serde(default, rename="attachment", skip_serializing_if="Vec::is_empty")] pub attachments: Vec<AttachmentSegment>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag="type")]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub enum ChatSegment {
     #[serde(rename="TEXT")]
     Text {
         text: String,
-        #[serde(default)] formatting: Formatting,
+        #[serde(default, skip_serializing_if="Formatting::is_plain")] formatting: Formatting,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
 
     #[serde(rename="LINK")]
     Link {
         text: String,
         link_data: LinkData,
-        #[serde(default)] formatting: Formatting,
+        #[serde(default, skip_serializing_if="Formatting::is_plain")] formatting: Formatting,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
 
     #[serde(rename="LINE_BREAK")]
     LineBreak {
-        text: Option<String>,
+        #[serde(skip_serializing_if="Option::is_none")] text: Option<String>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
+
+    #[serde(untagged)]
+    #[cfg_attr(not(feature = "lenient"), serde(skip_deserializing))]
+    Unknown(serde_json::Value),
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Annotation {
-    #[serde(rename="type")] pub typ: i32,
+    #[serde(rename="type")] pub typ: AnnotationKind,
     pub value: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+/// What an annotation on a chat message means. In the JSON these are numbers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(from="i32", into="i32")]
+pub enum AnnotationKind {
+    /// The message was sent with `/me`, so its text is something the sender did: "waves" for
+    /// "* Alice waves".
+    Action,
+    Other(i32),
+}
+
+impl From<i32> for AnnotationKind {
+    fn from(value: i32) -> Self {
+        match value {
+            4 => AnnotationKind::Action,
+            other => AnnotationKind::Other(other),
+        }
+    }
+}
+
+impl From<AnnotationKind> for i32 {
+    fn from(kind: AnnotationKind) -> Self {
+        match kind {
+            AnnotationKind::Action => 4,
+            AnnotationKind::Other(value) => value,
+        }
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct LinkData {
     pub link_target: String,
-    pub display_url: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub display_url: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Formatting {
-    #[serde(default)] pub bold: bool,
-    #[serde(default)] pub italics: bool,
-    #[serde(default)] pub strikethrough: bool,
-    #[serde(default)] pub underline: bool,
+    #[serde(default, skip_serializing_if="is_false")] pub bold: bool,
+    #[serde(default, skip_serializing_if="is_false")] pub italics: bool,
+    #[serde(default, skip_serializing_if="is_false")] pub strikethrough: bool,
+    #[serde(default, skip_serializing_if="is_false")] pub underline: bool,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+impl Formatting {
+    pub fn is_plain(&self) -> bool {
+        *self == Formatting::default()
+    }
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct AttachmentSegment {
     pub embed_item: EmbedItem,
     pub id: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct EmbedItem {
-    pub id: Option<String>,
-    pub plus_photo: Option<PlusPhoto>,
-    pub plus_audio_v2: Option<PlusAudioV2>,
-    pub place_v2: Option<PlaceV2>,
-    pub thing_v2: Option<ThingV2>,
+    #[serde(skip_serializing_if="Option::is_none")] pub id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub plus_photo: Option<PlusPhoto>,
+    #[serde(skip_serializing_if="Option::is_none")] pub plus_audio_v2: Option<PlusAudioV2>,
+    #[serde(skip_serializing_if="Option::is_none")] pub place_v2: Option<PlaceV2>,
+    #[serde(skip_serializing_if="Option::is_none")] pub thing_v2: Option<ThingV2>,
     #[serde(rename="type")] pub types: Vec<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct PlusPhoto {
     pub album_id: String,
-    pub media_type: String,
-    pub original_content_url: Option<String>,
-    pub owner_obfuscated_id: Option<String>,
+    pub media_type: PhotoMediaType,
+    #[serde(skip_serializing_if="Option::is_none")] pub original_content_url: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub owner_obfuscated_id: Option<String>,
     pub photo_id: String,
     pub stream_id: Vec<String>,
     pub thumbnail: Thumbnail,
     pub url: String,
-    pub download_url: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub download_url: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+string_enum! {
+    pub enum PhotoMediaType {
+        Photo = "PHOTO",
+        AnimatedPhoto = "ANIMATED_PHOTO",
+        Video = "VIDEO",
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Thumbnail {
     pub height_px: u64,
     pub width_px: u64,
     pub image_url: String,
-    pub url: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub url: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct PlaceV2 {
     pub url: String,
-    pub name: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub name: Option<String>,
     pub address: Address,
     pub geo: Geo,
     pub representative_image: RepresentativeImage,
-    pub place_id: Option<String>,
-    pub cluster_id: Option<String>,
-    pub reference_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub place_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub cluster_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub reference_id: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct PlusAudioV2 {
     pub album_id: String,
     pub duration: String,
     pub embed_url: String,
     pub media_key: String,
-    pub owner_obfuscated_id: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub owner_obfuscated_id: Option<String>,
     pub photo_id: String,
     pub url: String,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Address {
-    #[serde(rename="type", default)] pub types: Vec<String>,
+    #[serde(rename="type", default, skip_serializing_if="Vec::is_empty")] pub types: Vec<String>,
     pub postal_address_v2: PostalAddressV2,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct PostalAddressV2 {
-    pub name: Option<String>,
-    pub street_address: Option<String>,
-    pub address_locality: Option<String>,
-    pub address_region: Option<String>,
-    pub address_country: Option<String>,
-    pub postal_code: Option<String>,
-}
-
-#[derive(Deserialize, Debug)]
+    #[serde(skip_serializing_if="Option::is_none")] pub name: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub street_address: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub address_locality: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub address_region: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub address_country: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub postal_code: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct Geo {
-    #[serde(rename="type", default)] pub types: Vec<String>,
+    #[serde(rename="type", default, skip_serializing_if="Vec::is_empty")] pub types: Vec<String>,
     pub geo_coordinates_v2: GeoCoordinatesV2,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct GeoCoordinatesV2 {
     pub latitude: f64,
     pub longitude: f64,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct RepresentativeImage {
     #[serde(rename="type")] pub types: Vec<String>,
     pub id: String,
     pub image_object_v2: ImageObjectV2,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ImageObjectV2 {
     pub url: String,
-    pub width: Option<String>,
-    pub height: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub width: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub height: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub struct ThingV2 {
     pub url: String,
-    pub name: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")] pub name: Option<String>,
     pub representative_image: RepresentativeImage,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag="event_type")]
 #[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
 pub enum HangoutEvent {
-    #[serde(rename="START_HANGOUT")] StartHangout,
+    #[serde(rename="START_HANGOUT")] StartHangout {
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
     #[serde(rename="END_HANGOUT")] EndHangout {
         hangout_duration_secs: String,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
     },
+
+    #[serde(untagged)]
+    #[cfg_attr(not(feature = "lenient"), serde(skip_deserializing))]
+    Unknown(serde_json::Value),
 }
@@ -0,0 +1,731 @@
+//! The same types as `raw`, but with strings borrowed from the JSON where possible.
+//!
+//! Parsing a whole archive into `raw` allocates a `String` for every ID, name, and message. These
+//! types hold `Cow<'a, str>` instead, which borrows from the buffer being parsed unless the string
+//! contains escape sequences, making them much cheaper for scanning an archive that has been read
+//! into memory or memory-mapped:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), hangouts_json_parser::Error> {
+//! use hangouts_json_parser::raw::{self, borrowed};
+//!
+//! let json = std::fs::read("Hangouts.json")?;
+//! let hangouts = borrowed::Hangouts::from_slice(&json)?;
+//! let found = hangouts.conversations.into_iter()
+//!     .filter(|convo| convo.header.details.name.as_deref() == Some("Friends"))
+//!     .map(raw::Conversation::from)
+//!     .collect::<Vec<_>>();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the current layout of Hangouts.json is supported, not the one in `legacy`. Fields that
+//! hold no strings reuse the types from `raw`. With the "lenient" feature, events are parsed as
+//! `raw::Event` and converted, so they don't borrow.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::raw::{self, AnnotationKind, ConversationStatus, ConversationType, ConversationView,
+    EventType, Extra, ForceHistoryState, Formatting, GeoCoordinatesV2, GroupLinkSharingStatus,
+    MediaType, MediumType, MembershipChangeType, NetworkType, NotificationLevel, OtrStatus,
+    OtrToggle, PhotoMediaType};
+use crate::stream::Layout;
+use crate::{Error, Timestamp};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Hangouts<'a> {
+    #[serde(borrow)] pub conversations: Vec<Conversation<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+impl<'a> Hangouts<'a> {
+    /// Parse a whole Hangouts.json held in memory. Errors report the path of the offending node.
+    pub fn from_slice(json: &'a [u8]) -> Result<Self, Error> {
+        serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(json))
+            .map_err(|e| Error::Json {
+                path: e.path().to_string(),
+                conversation_id: conversation_id(json, e.path()),
+                source: e.into_inner(),
+            })
+    }
+}
+
+/// The ID of the conversation an error at `path` is in, if it's in one and the JSON is otherwise
+/// valid enough to find it.
+fn conversation_id(json: &[u8], path: &serde_path_to_error::Path) -> Option<String> {
+    use serde_path_to_error::Segment;
+
+    let mut segments = path.iter();
+    let index = match (segments.next(), segments.next()) {
+        (Some(Segment::Map { key }), Some(Segment::Seq { index })) if key == "conversations" => {
+            index
+        }
+        _ => return None,
+    };
+    let value: serde_json::Value = serde_json::from_slice(json).ok()?;
+    let pointer = format!("/conversations/{}{}", index, Layout::Current.id_pointer());
+    value.pointer(&pointer)?.as_str().map(str::to_owned)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Conversation<'a> {
+    #[serde(borrow, rename="conversation")] pub header: ConversationHeader<'a>,
+    #[serde(borrow)] pub events: Vec<Event<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ConversationHeader<'a> {
+    #[serde(borrow)] pub conversation_id: ConversationId<'a>,
+    #[serde(borrow, rename="conversation")] pub details: ConversationDetails<'a>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ConversationDetails<'a> {
+    #[serde(borrow)] pub id: ConversationId<'a>,
+    #[serde(rename="type")] pub typ: ConversationType,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub name: Option<Cow<'a, str>>,
+    #[serde(borrow)] pub self_conversation_state: SelfConversationState<'a>,
+    #[serde(borrow)] pub read_state: Vec<ReadState<'a>>,
+    pub has_active_hangout: bool,
+    pub otr_status: OtrStatus,
+    pub otr_toggle: OtrToggle,
+    #[serde(borrow)] pub current_participant: Vec<ParticipantId<'a>>,
+    #[serde(borrow)] pub participant_data: Vec<ParticipantData<'a>>,
+    pub fork_on_external_invite: bool,
+    pub network_type: Vec<NetworkType>,
+    pub force_history_state: ForceHistoryState,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub conversation_ttl_days: Option<Cow<'a, str>>,
+    pub group_link_sharing_status: GroupLinkSharingStatus,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ConversationId<'a> {
+    #[serde(borrow)] pub id: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct SelfConversationState<'a> {
+    #[serde(borrow)] pub self_read_state: ReadState<'a>,
+    pub status: ConversationStatus,
+    pub notification_level: NotificationLevel,
+    pub view: Vec<ConversationView>,
+    #[serde(borrow)] pub inviter_id: ParticipantId<'a>,
+    pub invite_timestamp: Timestamp,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub invitation_display_type: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub invite_affinity: Option<Cow<'a, str>>,
+    pub sort_timestamp: Timestamp,
+    #[serde(skip_serializing_if="Option::is_none")] pub active_timestamp: Option<Timestamp>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub delivery_medium_option: Option<Vec<DeliveryMediumOption<'a>>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_guest: Option<bool>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct DeliveryMediumOption<'a> {
+    #[serde(borrow)] pub delivery_medium: DeliveryMedium<'a>,
+    #[serde(skip_serializing_if="Option::is_none")] pub current_default: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub primary: Option<bool>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ReadState<'a> {
+    #[serde(borrow)] pub participant_id: ParticipantId<'a>,
+    pub latest_read_timestamp: Timestamp,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ParticipantId<'a> {
+    #[serde(borrow)] pub gaia_id: Cow<'a, str>,
+    #[serde(borrow)] pub chat_id: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ParticipantData<'a> {
+    #[serde(borrow)] pub id: ParticipantId<'a>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub fallback_name: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub invitation_status: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub participant_type: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub new_invitation_status: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub in_different_customer_as_requester: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_anonymous_phone: Option<bool>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub domain_id: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub phone_number: Option<PhoneNumber<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PhoneNumber<'a> {
+    #[serde(borrow)] pub e164: Cow<'a, str>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub i18n_data: Option<PhoneNumberI18nData<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PhoneNumberI18nData<'a> {
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub national_number: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub international_number: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub country_code: Option<u32>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub region_code: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub is_valid: Option<bool>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub validation_result: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "lenient", serde(from = "raw::Event"))]
+pub struct Event<'a> {
+    #[serde(borrow, flatten)] pub header: EventHeader<'a>,
+    #[serde(borrow, flatten, serialize_with="EventData::serialize_flattened")] pub data: EventData<'a>,
+    pub event_type: EventType,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct EventHeader<'a> {
+    #[serde(borrow)] pub conversation_id: ConversationId<'a>,
+    #[serde(borrow)] pub sender_id: ParticipantId<'a>,
+    pub timestamp: Timestamp,
+    #[serde(borrow)] pub self_event_state: SelfEventState<'a>,
+    #[serde(borrow)] pub event_id: Cow<'a, str>,
+    pub advances_sort_timestamp: bool,
+    pub event_otr: OtrStatus,
+    #[serde(borrow)] pub delivery_medium: DeliveryMedium<'a>,
+    #[serde(borrow)] pub event_version: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct DeliveryMedium<'a> {
+    pub medium_type: MediumType,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub self_phone: Option<PhoneNumber<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct SelfEventState<'a> {
+    #[serde(borrow)] pub user_id: ParticipantId<'a>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub client_generated_id: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if="Option::is_none")] pub notification_level: Option<NotificationLevel>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum EventData<'a> {
+    #[serde(rename="chat_message")]
+    ChatMessage {
+        #[serde(borrow)] message_content: ChatSegments<'a>,
+        #[serde(borrow, skip_serializing_if="Option::is_none")] annotation: Option<Vec<Annotation<'a>>>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(rename="conversation_rename")]
+    ConversationRename {
+        #[serde(borrow)] old_name: Cow<'a, str>,
+        #[serde(borrow)] new_name: Cow<'a, str>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(rename="hangout_event")]
+    HangoutEvent {
+        #[serde(borrow, flatten)] data: HangoutEvent<'a>,
+        #[serde(skip_serializing_if="Option::is_none")] media_type: Option<MediaType>,
+        #[serde(borrow, default, skip_serializing_if="Vec::is_empty")] participant_id: Vec<ParticipantId<'a>>,
+    },
+
+    #[serde(rename="membership_change")]
+    MembershipChange {
+        #[serde(rename="type")] typ: MembershipChangeType,
+        #[serde(borrow)] participant_id: Vec<ParticipantId<'a>>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    /// See `raw::EventData::Unknown`.
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+
+impl EventData<'_> {
+    fn serialize_flattened<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EventData::Unknown(value) => serde::Serialize::serialize(value, serializer),
+            _ => serde::Serialize::serialize(self, serializer),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ChatSegments<'a> {
+    #[serde(borrow, default, rename="segment", skip_serializing_if="Vec::is_empty")] pub segments: Vec<ChatSegment<'a>>,
+    #[serde(borrow, default, rename="attachment", skip_serializing_if="Vec::is_empty")] pub attachments: Vec<AttachmentSegment<'a>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag="type")]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub enum ChatSegment<'a> {
+    #[serde(rename="TEXT")]
+    Text {
+        #[serde(borrow)] text: Cow<'a, str>,
+        #[serde(default, skip_serializing_if="Formatting::is_plain")] formatting: Formatting,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(rename="LINK")]
+    Link {
+        #[serde(borrow)] text: Cow<'a, str>,
+        #[serde(borrow)] link_data: LinkData<'a>,
+        #[serde(default, skip_serializing_if="Formatting::is_plain")] formatting: Formatting,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(rename="LINE_BREAK")]
+    LineBreak {
+        #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] text: Option<Cow<'a, str>>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(untagged)]
+    #[cfg_attr(not(feature = "lenient"), serde(skip_deserializing))]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Annotation<'a> {
+    #[serde(rename="type")] pub typ: AnnotationKind,
+    #[serde(borrow)] pub value: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct LinkData<'a> {
+    #[serde(borrow)] pub link_target: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub display_url: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct AttachmentSegment<'a> {
+    #[serde(borrow)] pub embed_item: EmbedItem<'a>,
+    #[serde(borrow)] pub id: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct EmbedItem<'a> {
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub id: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub plus_photo: Option<PlusPhoto<'a>>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub plus_audio_v2: Option<PlusAudioV2<'a>>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub place_v2: Option<PlaceV2<'a>>,
+    #[serde(borrow, skip_serializing_if="Option::is_none")] pub thing_v2: Option<ThingV2<'a>>,
+    #[serde(borrow, rename="type", deserialize_with="list")] pub types: Vec<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PlusPhoto<'a> {
+    #[serde(borrow)] pub album_id: Cow<'a, str>,
+    pub media_type: PhotoMediaType,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub original_content_url: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub owner_obfuscated_id: Option<Cow<'a, str>>,
+    #[serde(borrow)] pub photo_id: Cow<'a, str>,
+    #[serde(borrow, deserialize_with="list")] pub stream_id: Vec<Cow<'a, str>>,
+    #[serde(borrow)] pub thumbnail: Thumbnail<'a>,
+    #[serde(borrow)] pub url: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub download_url: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Thumbnail<'a> {
+    pub height_px: u64,
+    pub width_px: u64,
+    #[serde(borrow)] pub image_url: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub url: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PlaceV2<'a> {
+    #[serde(borrow)] pub url: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub name: Option<Cow<'a, str>>,
+    #[serde(borrow)] pub address: Address<'a>,
+    #[serde(borrow)] pub geo: Geo<'a>,
+    #[serde(borrow)] pub representative_image: RepresentativeImage<'a>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub place_id: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub cluster_id: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub reference_id: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PlusAudioV2<'a> {
+    #[serde(borrow)] pub album_id: Cow<'a, str>,
+    #[serde(borrow)] pub duration: Cow<'a, str>,
+    #[serde(borrow)] pub embed_url: Cow<'a, str>,
+    #[serde(borrow)] pub media_key: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub owner_obfuscated_id: Option<Cow<'a, str>>,
+    #[serde(borrow)] pub photo_id: Cow<'a, str>,
+    #[serde(borrow)] pub url: Cow<'a, str>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Address<'a> {
+    #[serde(borrow, rename="type", default, deserialize_with="list", skip_serializing_if="Vec::is_empty")] pub types: Vec<Cow<'a, str>>,
+    #[serde(borrow)] pub postal_address_v2: PostalAddressV2<'a>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct PostalAddressV2<'a> {
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub name: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub street_address: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub address_locality: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub address_region: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub address_country: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub postal_code: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Geo<'a> {
+    #[serde(borrow, rename="type", default, deserialize_with="list", skip_serializing_if="Vec::is_empty")] pub types: Vec<Cow<'a, str>>,
+    pub geo_coordinates_v2: GeoCoordinatesV2,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct RepresentativeImage<'a> {
+    #[serde(borrow, rename="type", deserialize_with="list")] pub types: Vec<Cow<'a, str>>,
+    #[serde(borrow)] pub id: Cow<'a, str>,
+    #[serde(borrow)] pub image_object_v2: ImageObjectV2<'a>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ImageObjectV2<'a> {
+    #[serde(borrow)] pub url: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub width: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub height: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ThingV2<'a> {
+    #[serde(borrow)] pub url: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with="optional", skip_serializing_if="Option::is_none")] pub name: Option<Cow<'a, str>>,
+    #[serde(borrow)] pub representative_image: RepresentativeImage<'a>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag="event_type")]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub enum HangoutEvent<'a> {
+    #[serde(rename="START_HANGOUT")] StartHangout {
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+    #[serde(rename="END_HANGOUT")] EndHangout {
+        #[serde(borrow)] hangout_duration_secs: Cow<'a, str>,
+        #[cfg_attr(feature = "lenient", serde(flatten))]
+        #[cfg_attr(not(feature = "lenient"), serde(skip))]
+        extra: Extra,
+    },
+
+    #[serde(untagged)]
+    #[cfg_attr(not(feature = "lenient"), serde(skip_deserializing))]
+    Unknown(serde_json::Value),
+}
+
+/// `serde(borrow)` only borrows a bare `Cow<str>`, not one inside an `Option` or `Vec`, so those
+/// go through this.
+#[derive(Deserialize)]
+struct BorrowedStr<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn optional<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D)
+    -> Result<Option<Cow<'a, str>>, D::Error>
+{
+    Ok(Option::<BorrowedStr<'a>>::deserialize(deserializer)?.map(|s| s.0))
+}
+
+fn list<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error> {
+    Ok(Vec::<BorrowedStr<'a>>::deserialize(deserializer)?.into_iter().map(|s| s.0).collect())
+}
+
+/// Conversion between the borrowed types and their counterparts in `raw`, in either direction.
+trait Convert<T> {
+    fn convert(self) -> T;
+}
+
+impl<'a> Convert<String> for Cow<'a, str> {
+    fn convert(self) -> String {
+        self.into_owned()
+    }
+}
+
+impl<'a> Convert<Cow<'a, str>> for String {
+    fn convert(self) -> Cow<'a, str> {
+        Cow::Owned(self)
+    }
+}
+
+impl<T: Convert<U>, U> Convert<Option<U>> for Option<T> {
+    fn convert(self) -> Option<U> {
+        self.map(Convert::convert)
+    }
+}
+
+impl<T: Convert<U>, U> Convert<Vec<U>> for Vec<T> {
+    fn convert(self) -> Vec<U> {
+        self.into_iter().map(Convert::convert).collect()
+    }
+}
+
+/// Types shared with `raw`.
+macro_rules! same {
+    ($($ty:ty),*) => {
+        $(
+            impl Convert<$ty> for $ty {
+                fn convert(self) -> $ty {
+                    self
+                }
+            }
+        )*
+    }
+}
+
+same!(bool, i32, u32, u64, Extra, serde_json::Value, Timestamp, AnnotationKind,
+    ConversationStatus, ConversationType, ConversationView, EventType, ForceHistoryState,
+    Formatting, GeoCoordinatesV2, GroupLinkSharingStatus, MediaType, MediumType,
+    MembershipChangeType, NetworkType, NotificationLevel, OtrStatus, OtrToggle, PhotoMediaType);
+
+/// Implement `From` both ways between a struct and the one of the same name in `raw`, given all
+/// of its fields.
+macro_rules! convert_struct {
+    ($ty:ident { $($field:ident),* }) => {
+        impl<'a> From<$ty<'a>> for raw::$ty {
+            fn from(value: $ty<'a>) -> Self {
+                raw::$ty { $($field: value.$field.convert(),)* }
+            }
+        }
+
+        impl<'a> From<raw::$ty> for $ty<'a> {
+            fn from(value: raw::$ty) -> Self {
+                $ty { $($field: value.$field.convert(),)* }
+            }
+        }
+
+        impl<'a> Convert<raw::$ty> for $ty<'a> {
+            fn convert(self) -> raw::$ty {
+                self.into()
+            }
+        }
+
+        impl<'a> Convert<$ty<'a>> for raw::$ty {
+            fn convert(self) -> $ty<'a> {
+                self.into()
+            }
+        }
+    }
+}
+
+convert_struct!(Hangouts { conversations, extra });
+convert_struct!(Conversation { header, events, extra });
+convert_struct!(ConversationHeader { conversation_id, details, extra });
+convert_struct!(ConversationDetails { id, typ, name, self_conversation_state, read_state,
+    has_active_hangout, otr_status, otr_toggle, current_participant, participant_data,
+    fork_on_external_invite, network_type, force_history_state, conversation_ttl_days,
+    group_link_sharing_status, extra });
+convert_struct!(ConversationId { id, extra });
+convert_struct!(SelfConversationState { self_read_state, status, notification_level, view,
+    inviter_id, invite_timestamp, invitation_display_type, invite_affinity, sort_timestamp,
+    active_timestamp, delivery_medium_option, is_guest, extra });
+convert_struct!(DeliveryMediumOption { delivery_medium, current_default, primary, extra });
+convert_struct!(ReadState { participant_id, latest_read_timestamp, extra });
+convert_struct!(ParticipantId { gaia_id, chat_id, extra });
+convert_struct!(ParticipantData { id, fallback_name, invitation_status, participant_type,
+    new_invitation_status, in_different_customer_as_requester, is_anonymous_phone, domain_id,
+    phone_number, extra });
+convert_struct!(PhoneNumber { e164, i18n_data, extra });
+convert_struct!(PhoneNumberI18nData { national_number, international_number, country_code,
+    region_code, is_valid, validation_result, extra });
+convert_struct!(Event { header, data, event_type, extra });
+convert_struct!(EventHeader { conversation_id, sender_id, timestamp, self_event_state, event_id,
+    advances_sort_timestamp, event_otr, delivery_medium, event_version, extra });
+convert_struct!(DeliveryMedium { medium_type, self_phone, extra });
+convert_struct!(SelfEventState { user_id, client_generated_id, notification_level, extra });
+convert_struct!(ChatSegments { segments, attachments, extra });
+convert_struct!(Annotation { typ, value, extra });
+convert_struct!(LinkData { link_target, display_url, extra });
+convert_struct!(AttachmentSegment { embed_item, id, extra });
+convert_struct!(EmbedItem { id, plus_photo, plus_audio_v2, place_v2, thing_v2, types, extra });
+convert_struct!(PlusPhoto { album_id, media_type, original_content_url, owner_obfuscated_id,
+    photo_id, stream_id, thumbnail, url, download_url, extra });
+convert_struct!(Thumbnail { height_px, width_px, image_url, url, extra });
+convert_struct!(PlaceV2 { url, name, address, geo, representative_image, place_id, cluster_id,
+    reference_id, extra });
+convert_struct!(PlusAudioV2 { album_id, duration, embed_url, media_key, owner_obfuscated_id,
+    photo_id, url, extra });
+convert_struct!(Address { types, postal_address_v2, extra });
+convert_struct!(PostalAddressV2 { name, street_address, address_locality, address_region,
+    address_country, postal_code, extra });
+convert_struct!(Geo { types, geo_coordinates_v2, extra });
+convert_struct!(RepresentativeImage { types, id, image_object_v2, extra });
+convert_struct!(ImageObjectV2 { url, width, height, extra });
+convert_struct!(ThingV2 { url, name, representative_image, extra });
+
+/// Implement `Convert` both ways between an enum and the one of the same name in `raw`, given
+/// its variants and their fields.
+macro_rules! convert_enum {
+    ($ty:ident { $($variant:ident { $($field:ident),* }),* }) => {
+        impl<'a> Convert<raw::$ty> for $ty<'a> {
+            fn convert(self) -> raw::$ty {
+                match self {
+                    $($ty::$variant { $($field),* } => raw::$ty::$variant { $($field: $field.convert()),* },)*
+                    $ty::Unknown(value) => raw::$ty::Unknown(value),
+                }
+            }
+        }
+
+        impl<'a> Convert<$ty<'a>> for raw::$ty {
+            fn convert(self) -> $ty<'a> {
+                match self {
+                    $(raw::$ty::$variant { $($field),* } => $ty::$variant { $($field: $field.convert()),* },)*
+                    raw::$ty::Unknown(value) => $ty::Unknown(value),
+                }
+            }
+        }
+    }
+}
+
+convert_enum!(EventData {
+    ChatMessage { message_content, annotation, extra },
+    ConversationRename { old_name, new_name, extra },
+    HangoutEvent { data, media_type, participant_id },
+    MembershipChange { typ, participant_id, extra }
+});
+convert_enum!(ChatSegment {
+    Text { text, formatting, extra },
+    Link { text, link_data, formatting, extra },
+    LineBreak { text, extra }
+});
+convert_enum!(HangoutEvent {
+    StartHangout { extra },
+    EndHangout { hangout_duration_secs, extra }
+});
@@ -0,0 +1,67 @@
+//! The layout of Hangouts.json in Takeout exports from before 2019.
+//!
+//! Conversations and events have the same contents as in the current layout, but each
+//! conversation is wrapped in a `conversation_state` object, alongside some metadata from the API
+//! it was fetched from, and the arrays are named differently. These types convert into the
+//! current ones; `Hangouts::from_reader` and friends detect the layout and do so automatically.
+
+use crate::raw::{self, ConversationDetails, ConversationId, Event, Extra};
+use crate::Timestamp;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct Hangouts {
+    #[serde(skip_serializing_if="Option::is_none")] pub continuation_end_timestamp: Option<Timestamp>,
+    pub conversation_state: Vec<ConversationEntry>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ConversationEntry {
+    pub conversation_id: ConversationId,
+    pub conversation_state: ConversationState,
+    #[serde(skip_serializing_if="Option::is_none")] pub response_header: Option<serde_json::Value>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "deny_unknown_fields", serde(deny_unknown_fields))]
+pub struct ConversationState {
+    pub conversation_id: ConversationId,
+    pub conversation: ConversationDetails,
+    #[serde(default, skip_serializing_if="Vec::is_empty")] pub event: Vec<Event>,
+    #[serde(skip_serializing_if="Option::is_none")] pub event_continuation_token: Option<serde_json::Value>,
+    #[cfg_attr(feature = "lenient", serde(flatten))]
+    #[cfg_attr(not(feature = "lenient"), serde(skip))]
+    pub extra: Extra,
+}
+
+/// The API metadata (`response_header` and `event_continuation_token`) is dropped.
+impl From<ConversationEntry> for raw::Conversation {
+    fn from(entry: ConversationEntry) -> Self {
+        let state = entry.conversation_state;
+        raw::Conversation {
+            header: raw::ConversationHeader {
+                conversation_id: entry.conversation_id,
+                details: state.conversation,
+                extra: state.extra,
+            },
+            events: state.event,
+            extra: entry.extra,
+        }
+    }
+}
+
+impl From<Hangouts> for raw::Hangouts {
+    fn from(legacy: Hangouts) -> Self {
+        raw::Hangouts {
+            conversations: legacy.conversation_state.into_iter().map(raw::Conversation::from).collect(),
+            extra: legacy.extra,
+        }
+    }
+}
@@ -0,0 +1,475 @@
+//! Rendering message bodies as plain text, HTML, Markdown, or for a terminal.
+//!
+//! A `MessageBody` is a message's segments and attachments, with the details of the JSON taken
+//! out: each span of text has a `Style`, links have their targets, and line breaks are just line
+//! breaks. A `Renderer` turns one into a string; each output format implements the handful of
+//! methods for the kinds of node, and gets `render` for free.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::model::{Attachment, Message};
+use crate::raw;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageBody<'a> {
+    pub nodes: Vec<Node<'a>>,
+    /// Whether this is a `/me` message, to be shown after the sender's name.
+    pub action: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node<'a> {
+    Text { text: &'a str, style: Style },
+    Link(Link<'a>),
+    LineBreak,
+    Attachment(&'a Attachment),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link<'a> {
+    pub text: &'a str,
+    pub target: &'a str,
+    /// A shortened form of the target, for showing to people, if Hangouts provided one.
+    pub display_url: Option<&'a str>,
+    pub style: Style,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    pub fn is_plain(self) -> bool {
+        self == Style::default()
+    }
+}
+
+impl From<&raw::Formatting> for Style {
+    fn from(formatting: &raw::Formatting) -> Self {
+        Style {
+            bold: formatting.bold,
+            italic: formatting.italics,
+            strikethrough: formatting.strikethrough,
+            underline: formatting.underline,
+        }
+    }
+}
+
+impl<'a> MessageBody<'a> {
+    /// The body of a message without its attachments. Segments of unknown types are left out.
+    pub fn from_segments(segments: &'a [raw::ChatSegment]) -> Self {
+        let nodes = segments.iter()
+            .filter_map(|segment| Some(match segment {
+                raw::ChatSegment::Text { text, formatting, .. } => {
+                    Node::Text { text, style: Style::from(formatting) }
+                }
+                raw::ChatSegment::Link { text, link_data, formatting, .. } => Node::Link(Link {
+                    text,
+                    target: &link_data.link_target,
+                    display_url: link_data.display_url.as_deref(),
+                    style: Style::from(formatting),
+                }),
+                raw::ChatSegment::LineBreak { .. } => Node::LineBreak,
+                raw::ChatSegment::Unknown(_) => return None,
+            }))
+            .collect();
+        MessageBody { nodes, action: false }
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = &'a Attachment> + '_ {
+        self.nodes.iter().filter_map(|node| match *node {
+            Node::Attachment(attachment) => Some(attachment),
+            _ => None,
+        })
+    }
+}
+
+impl Message {
+    /// The message's text followed by its attachments.
+    pub fn body(&self) -> MessageBody<'_> {
+        let mut body = MessageBody::from_segments(&self.segments);
+        body.action = self.is_action();
+        body.nodes.extend(self.attachments.iter().map(Node::Attachment));
+        body
+    }
+}
+
+pub trait Renderer {
+    fn text(&self, out: &mut String, text: &str, style: Style);
+    fn link(&self, out: &mut String, link: &Link<'_>);
+    fn line_break(&self, out: &mut String);
+    fn attachment(&self, out: &mut String, attachment: &Attachment);
+
+    /// What comes before a message from the given sender: `Alice: `.
+    fn sender(&self, out: &mut String, name: &str) {
+        self.text(out, name, Style { bold: true, ..Style::default() });
+        self.text(out, ": ", Style::default());
+    }
+
+    /// What comes before a `/me` message from the given sender: `* Alice `.
+    fn action(&self, out: &mut String, name: &str) {
+        self.text(out, "* ", Style::default());
+        self.text(out, name, Style { bold: true, ..Style::default() });
+        self.text(out, " ", Style::default());
+    }
+
+    /// The body alone, without the sender, even if it's an action.
+    fn render(&self, body: &MessageBody<'_>) -> String {
+        let mut out = String::new();
+        self.render_nodes(&mut out, body);
+        out
+    }
+
+    /// The body along with who sent it: `Alice: hi`, or for an action, `* Alice waves`.
+    fn render_message(&self, sender: &str, body: &MessageBody<'_>) -> String {
+        let mut out = String::new();
+        if body.action {
+            self.action(&mut out, sender);
+        } else {
+            self.sender(&mut out, sender);
+        }
+        self.render_nodes(&mut out, body);
+        out
+    }
+
+    fn render_nodes(&self, out: &mut String, body: &MessageBody<'_>) {
+        for node in &body.nodes {
+            match node {
+                Node::Text { text, style } => self.text(out, text, *style),
+                Node::Link(link) => self.link(out, link),
+                Node::LineBreak => self.line_break(out),
+                Node::Attachment(attachment) => self.attachment(out, attachment),
+            }
+        }
+    }
+}
+
+/// How an attachment is described where it can't be shown: its kind, and where it can be found.
+fn attachment_label(attachment: &Attachment) -> String {
+    let kind = attachment.media_type().to_lowercase();
+    match (attachment, attachment.url()) {
+        (Attachment::File(file), _) => format!("[{}: {}]", kind, file.original_name),
+        (_, Some(url)) => format!("[{}: {}]", kind, url),
+        (_, None) => format!("[{}]", kind),
+    }
+}
+
+/// Text only: formatting is dropped, and links are reduced to their text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainText;
+
+impl Renderer for PlainText {
+    fn text(&self, out: &mut String, text: &str, _style: Style) {
+        out.push_str(text);
+    }
+
+    fn link(&self, out: &mut String, link: &Link<'_>) {
+        out.push_str(link.text);
+    }
+
+    fn line_break(&self, out: &mut String) {
+        out.push('\n');
+    }
+
+    fn attachment(&self, out: &mut String, attachment: &Attachment) {
+        out.push_str(&attachment_label(attachment));
+    }
+}
+
+/// An HTML fragment, with all text escaped. Links to anything other than web pages, e-mail
+/// addresses, and phone numbers are shown as text instead, so a message can't run script.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Html;
+
+impl Html {
+    fn open(out: &mut String, style: Style) {
+        if style.bold { out.push_str("<b>"); }
+        if style.italic { out.push_str("<i>"); }
+        if style.strikethrough { out.push_str("<s>"); }
+        if style.underline { out.push_str("<u>"); }
+    }
+
+    fn close(out: &mut String, style: Style) {
+        if style.underline { out.push_str("</u>"); }
+        if style.strikethrough { out.push_str("</s>"); }
+        if style.italic { out.push_str("</i>"); }
+        if style.bold { out.push_str("</b>"); }
+    }
+}
+
+impl Renderer for Html {
+    fn text(&self, out: &mut String, text: &str, style: Style) {
+        Html::open(out, style);
+        out.push_str(&escape_html(text));
+        Html::close(out, style);
+    }
+
+    fn link(&self, out: &mut String, link: &Link<'_>) {
+        Html::open(out, link.style);
+        if is_safe_url(link.target) {
+            out.push_str("<a href=\"");
+            out.push_str(&escape_html(link.target));
+            out.push_str("\">");
+            out.push_str(&escape_html(link.text));
+            out.push_str("</a>");
+        } else {
+            out.push_str(&escape_html(link.text));
+        }
+        Html::close(out, link.style);
+    }
+
+    fn line_break(&self, out: &mut String) {
+        out.push_str("<br>\n");
+    }
+
+    fn attachment(&self, out: &mut String, attachment: &Attachment) {
+        let url = attachment.url().filter(|url| is_safe_url(url));
+        match (attachment, url) {
+            (Attachment::Photo(photo), Some(url)) if is_safe_url(&photo.thumbnail.url) => {
+                out.push_str(&format!("<a href=\"{}\"><img src=\"{}\" alt=\"photo\"></a>",
+                    escape_html(url), escape_html(&photo.thumbnail.url)));
+            }
+            (Attachment::File(file), _) => {
+                let href = relative_url(&file.export_name);
+                out.push_str(&format!("<a href=\"{}\">{}</a>",
+                    escape_html(&href), escape_html(&file.original_name)));
+            }
+            (_, Some(url)) => {
+                out.push_str(&format!("<a href=\"{}\">[{}]</a>",
+                    escape_html(url), escape_html(&attachment.media_type().to_lowercase())));
+            }
+            (_, None) => out.push_str(&escape_html(&attachment_label(attachment))),
+        }
+    }
+}
+
+/// Escape text for use in HTML, either as content or in a quoted attribute value.
+pub fn escape_html(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Whether a URL can be linked to without it running anything.
+pub(crate) fn is_safe_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    ["http://", "https://", "mailto:", "tel:"].iter().any(|scheme| url.starts_with(scheme))
+}
+
+/// A URL for a file name relative to the page: percent-encoded, and starting with "./" so that a
+/// colon in it can't be taken for a scheme.
+pub(crate) fn relative_url(name: &str) -> String {
+    format!("./{}", percent_encode(name))
+}
+
+/// Percent-encode everything but letters, digits, slashes, and the few punctuation characters that
+/// never need it, for using a file path in a URL.
+pub fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// A `file://` URL for an absolute path, or `None` if the path isn't UTF-8.
+pub fn file_url(path: &Path) -> Option<String> {
+    let s = path.to_str()?;
+    let s = if cfg!(windows) {
+        // Browsers don't like "\\?\" paths; remove the prefix.
+        s.strip_prefix(r"\\?\").unwrap_or(s).replace('\\', "/")
+    } else {
+        s.to_owned()
+    };
+    // A Windows drive letter's colon stays as it is.
+    let (drive, rest) = match s.find(':') {
+        Some(i) if cfg!(windows) => s.split_at(i + 1),
+        _ => ("", &s[..]),
+    };
+    let slash = if s.starts_with('/') { "" } else { "/" };
+    Some(format!("file://{}{}{}", slash, drive, percent_encode(rest)))
+}
+
+/// CommonMark. Links are only made to the same kinds of URL as with `Html`, since Markdown is
+/// usually turned into HTML. Underlining, which Markdown has no syntax for, is done with an HTML
+/// tag.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+impl Markdown {
+    fn styled(out: &mut String, text: &str, style: Style) {
+        // Emphasis markers have to be right up against the text they surround.
+        let trimmed = text.trim();
+        if trimmed.is_empty() || style.is_plain() {
+            out.push_str(text);
+            return;
+        }
+        let start = text.len() - text.trim_start().len();
+        out.push_str(&text[..start]);
+        let markers = [
+            (style.bold, "**", "**"),
+            (style.italic, "*", "*"),
+            (style.strikethrough, "~~", "~~"),
+            (style.underline, "<u>", "</u>"),
+        ];
+        for &(on, open, _) in &markers {
+            if on { out.push_str(open); }
+        }
+        out.push_str(trimmed);
+        for &(on, _, close) in markers.iter().rev() {
+            if on { out.push_str(close); }
+        }
+        out.push_str(&text[start + trimmed.len()..]);
+    }
+}
+
+impl Renderer for Markdown {
+    fn text(&self, out: &mut String, text: &str, style: Style) {
+        Markdown::styled(out, &escape_markdown(text), style);
+    }
+
+    fn link(&self, out: &mut String, link: &Link<'_>) {
+        let text = escape_markdown(link.text);
+        if is_safe_url(link.target) {
+            let linked = format!("[{}](<{}>)", text, markdown_url(link.target));
+            Markdown::styled(out, &linked, link.style);
+        } else {
+            Markdown::styled(out, &text, link.style);
+        }
+    }
+
+    fn line_break(&self, out: &mut String) {
+        out.push_str("\\\n");
+    }
+
+    fn attachment(&self, out: &mut String, attachment: &Attachment) {
+        let kind = attachment.media_type().to_lowercase();
+        let url = attachment.url().filter(|url| is_safe_url(url));
+        match (attachment, url) {
+            (Attachment::Photo(photo), Some(url)) if is_safe_url(&photo.thumbnail.url) => {
+                out.push_str(&format!("[![{}](<{}>)](<{}>)",
+                    kind, markdown_url(&photo.thumbnail.url), markdown_url(url)));
+            }
+            (Attachment::File(file), _) => {
+                out.push_str(&format!("[{}](<{}>)",
+                    escape_markdown(&file.original_name), relative_url(&file.export_name)));
+            }
+            (_, Some(url)) => out.push_str(&format!("[\\[{}\\]](<{}>)", kind, markdown_url(url))),
+            (_, None) => out.push_str(&escape_markdown(&attachment_label(attachment))),
+        }
+    }
+}
+
+/// Backslash-escape the characters that could otherwise start Markdown syntax, including the
+/// markers of headings, lists, and quotes at the start of a line. The start of `text` counts as
+/// the start of a line, since it may end up at one.
+pub fn escape_markdown(text: &str) -> Cow<'_, str> {
+    const SPECIAL: &[char] = &['\\', '`', '*', '_', '[', ']', '<', '>', '#', '!', '~', '|'];
+    let mut escaped: Option<String> = None;
+    let mut line_start = true;
+    // Whether this is in the number of what could be an ordered list item, like "1.".
+    let mut number = false;
+    for (i, c) in text.char_indices() {
+        let escape = SPECIAL.contains(&c)
+            || (line_start && matches!(c, '-' | '+' | '='))
+            || (number && matches!(c, '.' | ')'));
+        if escape {
+            escaped.get_or_insert_with(|| {
+                let mut escaped = String::with_capacity(text.len() + 8);
+                escaped.push_str(&text[..i]);
+                escaped
+            }).push('\\');
+        }
+        if let Some(ref mut escaped) = escaped {
+            escaped.push(c);
+        }
+        number = c.is_ascii_digit() && (line_start || number);
+        line_start = c == '\n' || (line_start && matches!(c, ' ' | '\t'));
+    }
+    escaped.map_or(Cow::Borrowed(text), Cow::Owned)
+}
+
+/// A URL as the destination of a Markdown link, which goes between angle brackets so it can have
+/// spaces and parentheses in it, but not line breaks or more angle brackets.
+fn markdown_url(url: &str) -> String {
+    url.replace(['<', '>', '\n', '\r'], "")
+}
+
+/// Text for a terminal, with formatting done with ANSI escape codes. Links are underlined, with
+/// their target after them if it isn't the same as the text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ansi;
+
+impl Ansi {
+    fn styled(out: &mut String, text: &str, style: Style, extra: &[&str]) {
+        let codes = [
+            (style.bold, "1"),
+            (style.italic, "3"),
+            (style.underline, "4"),
+            (style.strikethrough, "9"),
+        ].iter()
+            .filter(|(on, _)| *on)
+            .map(|&(_, code)| code)
+            .chain(extra.iter().copied())
+            .collect::<Vec<_>>();
+        if codes.is_empty() {
+            out.push_str(text);
+        } else {
+            out.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text));
+        }
+    }
+}
+
+impl Renderer for Ansi {
+    fn text(&self, out: &mut String, text: &str, style: Style) {
+        Ansi::styled(out, &strip_escapes(text), style, &[]);
+    }
+
+    fn link(&self, out: &mut String, link: &Link<'_>) {
+        Ansi::styled(out, &strip_escapes(link.text), link.style, &["4", "34"]);
+        let shown = link.display_url.unwrap_or(link.target);
+        if link.text != link.target && link.text != shown {
+            out.push_str(&format!(" <{}>", strip_escapes(shown)));
+        }
+    }
+
+    fn line_break(&self, out: &mut String) {
+        out.push('\n');
+    }
+
+    fn attachment(&self, out: &mut String, attachment: &Attachment) {
+        Ansi::styled(out, &strip_escapes(&attachment_label(attachment)), Style::default(), &["2"]);
+    }
+}
+
+/// Remove control characters other than newlines and tabs, so a message can't send the terminal
+/// escape sequences of its own.
+fn strip_escapes(text: &str) -> Cow<'_, str> {
+    let bad = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if text.contains(bad) {
+        Cow::Owned(text.chars().filter(|&c| !bad(c)).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
@@ -0,0 +1,416 @@
+//! Finding the local copies of attachments that Takeout stores alongside Hangouts.json.
+//!
+//! Attachment URLs point at Google's servers, but the files themselves are usually in the same
+//! folder as Hangouts.json, under a name derived (not always consistently) from the last part of
+//! the URL. The folder can be read from disk directly, or from inside a Takeout zip or tarball.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::{fs::File, io::{Seek, SeekFrom}};
+
+use crate::model;
+
+/// Where Takeout archives put Hangouts.json and the attachment files.
+pub const TAKEOUT_PREFIX: &str = "Takeout/Hangouts/";
+
+/// A collection of files which attachments can be found in, by name.
+pub trait FileSource {
+    fn contains(&self, name: &str) -> bool;
+
+    /// Read the whole contents of the named file.
+    fn read(&self, name: &str) -> io::Result<Vec<u8>>;
+
+    /// Open the named file for reading incrementally. By default this reads the whole file up
+    /// front.
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(io::Cursor::new(self.read(name)?)))
+    }
+}
+
+impl<S: FileSource + ?Sized> FileSource for &S {
+    fn contains(&self, name: &str) -> bool {
+        (**self).contains(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        (**self).read(name)
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        (**self).reader(name)
+    }
+}
+
+impl<S: FileSource + ?Sized> FileSource for Box<S> {
+    fn contains(&self, name: &str) -> bool {
+        (**self).contains(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        (**self).read(name)
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        (**self).reader(name)
+    }
+}
+
+/// The files directly inside a directory on disk.
+#[derive(Debug, Clone)]
+pub struct Directory {
+    root: PathBuf,
+    names: HashSet<String>,
+}
+
+impl Directory {
+    /// List the files in the given directory. Files added to it later are not seen.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        let mut names = HashSet::new();
+        for entry in fs::read_dir(&root)? {
+            let entry = entry?;
+            if !entry.path().is_file() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                names.insert(name);
+            }
+        }
+        Ok(Directory { root, names })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The full path to the named file.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+}
+
+impl FileSource for Directory {
+    fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        if !self.contains(name) {
+            return Err(not_found(name));
+        }
+        fs::read(self.path(name))
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        if !self.contains(name) {
+            return Err(not_found(name));
+        }
+        Ok(Box::new(fs::File::open(self.path(name))?))
+    }
+}
+
+/// The files under a given folder in a zip archive.
+#[cfg(feature = "zip")]
+#[derive(Debug)]
+pub struct ZipSource {
+    path: PathBuf,
+    archive: std::cell::RefCell<zip::ZipArchive<File>>,
+    prefix: String,
+    names: HashSet<String>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSource {
+    /// Index the files in the archive under `prefix`, such as `TAKEOUT_PREFIX`.
+    pub fn open(path: impl Into<PathBuf>, prefix: &str) -> io::Result<Self> {
+        let path = path.into();
+        let archive = zip::ZipArchive::new(File::open(&path)?)?;
+        let prefix = folder_prefix(prefix);
+        let names = archive.file_names()
+            .filter(|name| !name.ends_with('/'))
+            .filter_map(|name| name.strip_prefix(prefix.as_str()))
+            .map(str::to_owned)
+            .collect();
+        Ok(ZipSource { path, archive: std::cell::RefCell::new(archive), prefix, names })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl FileSource for ZipSource {
+    fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        if !self.contains(name) {
+            return Err(not_found(name));
+        }
+        let mut archive = self.archive.borrow_mut();
+        let mut file = archive.by_name(&format!("{}{}", self.prefix, name))?;
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Reads the compressed data directly from a separate handle on the archive file, so that
+    /// the reader doesn't hold a borrow of the archive.
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        if !self.contains(name) {
+            return Err(not_found(name));
+        }
+        let (start, size, method) = {
+            let mut archive = self.archive.borrow_mut();
+            let file = archive.by_name(&format!("{}{}", self.prefix, name))?;
+            (file.data_start(), file.compressed_size(), file.compression())
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let data = io::BufReader::new(file).take(size);
+        match method {
+            zip::CompressionMethod::Stored => Ok(Box::new(data)),
+            zip::CompressionMethod::Deflated => Ok(Box::new(flate2::bufread::DeflateDecoder::new(data))),
+            other => Err(io::Error::new(io::ErrorKind::Unsupported,
+                format!("unsupported compression method {} for {:?}", other, name))),
+        }
+    }
+}
+
+/// The files under a given folder in a tar archive, which may be gzipped.
+///
+/// Gzipped tar archives can't be seeked in, so each read decompresses the archive up to the
+/// requested file.
+#[cfg(feature = "tar")]
+#[derive(Debug, Clone)]
+pub struct TarSource {
+    path: PathBuf,
+    /// Offset of each file's data in the (uncompressed) archive, and its size.
+    files: HashMap<String, (u64, u64)>,
+}
+
+#[cfg(feature = "tar")]
+impl TarSource {
+    /// Index the files in the archive under `prefix`, such as `TAKEOUT_PREFIX`.
+    pub fn open(path: impl Into<PathBuf>, prefix: &str) -> io::Result<Self> {
+        let path = path.into();
+        let prefix = folder_prefix(prefix);
+        let mut files = HashMap::new();
+        for entry in tar::Archive::new(open_tar(&path, 0)?).entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if let Some(name) = tar_entry_name(&entry)?.strip_prefix(prefix.as_str()) {
+                files.insert(name.to_owned(), (entry.raw_file_position(), entry.size()));
+            }
+        }
+        Ok(TarSource { path, files })
+    }
+}
+
+#[cfg(feature = "tar")]
+impl FileSource for TarSource {
+    fn contains(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let size = self.files.get(name).map_or(0, |&(_, size)| size);
+        let mut data = Vec::with_capacity(size as usize);
+        self.reader(name)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        let (offset, size) = *self.files.get(name).ok_or_else(|| not_found(name))?;
+        Ok(Box::new(open_tar(&self.path, offset)?.take(size)))
+    }
+}
+
+/// Open a possibly-gzipped tar file, positioned at the given offset in the uncompressed data.
+#[cfg(feature = "tar")]
+fn open_tar(path: &Path, offset: u64) -> io::Result<Box<dyn Read>> {
+    let mut file = io::BufReader::new(File::open(path)?);
+    if !io::BufRead::fill_buf(&mut file)?.starts_with(&[0x1f, 0x8b]) {
+        file.seek(SeekFrom::Start(offset))?;
+        return Ok(Box::new(file));
+    }
+    let mut reader = flate2::bufread::MultiGzDecoder::new(file);
+    io::copy(&mut (&mut reader).take(offset), &mut io::sink())?;
+    Ok(Box::new(reader))
+}
+
+#[cfg(feature = "tar")]
+fn tar_entry_name<R: Read>(entry: &tar::Entry<'_, R>) -> io::Result<String> {
+    let path = entry.path()?;
+    let name = path.to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "non-UTF-8 path in tar archive"))?;
+    Ok(name.trim_start_matches("./").to_owned())
+}
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn folder_prefix(prefix: &str) -> String {
+    let mut prefix = prefix.trim_start_matches('/').to_owned();
+    if !prefix.is_empty() && !prefix.ends_with('/') {
+        prefix.push('/');
+    }
+    prefix
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no file named {:?}", name))
+}
+
+/// Matches attachments up with the files in a `FileSource`.
+#[derive(Debug)]
+pub struct AttachmentResolver<S> {
+    source: S,
+}
+
+impl<S: FileSource> AttachmentResolver<S> {
+    pub fn new(source: S) -> Self {
+        AttachmentResolver { source }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn into_source(self) -> S {
+        self.source
+    }
+
+    /// Find the file for an attachment URL, returning its name in the source.
+    pub fn resolve_url(&self, url: &str) -> Option<String> {
+        let url_filename = url.rsplit_terminator('/').next()?;
+        let decoded = urldecode(url_filename)?;
+        self.search(decoded.clone(), false)
+            // Try again, additionally replacing some characters because this is what Google
+            // does sometimes.
+            .or_else(|| self.search(decoded, true))
+    }
+
+    /// Find the file for an attachment. Only photos, videos, audio, and uploaded files have files.
+    pub fn resolve(&self, attachment: &model::Attachment) -> Option<String> {
+        match attachment {
+            model::Attachment::File(file) if self.source.contains(&file.export_name) => {
+                Some(file.export_name.clone())
+            }
+            attachment => self.resolve_url(local_url(attachment)?),
+        }
+    }
+
+    /// Try to find files for every attachment in the archive.
+    pub fn resolve_archive(&self, archive: &model::Archive) -> Resolution {
+        let mut resolution = Resolution::default();
+        let mut missing = HashSet::new();
+        for convo in &archive.conversations {
+            for (event, message) in convo.messages() {
+                for attachment in &message.attachments {
+                    let key = match attachment_key(attachment) {
+                        Some(key) if !resolution.found.contains_key(key)
+                            && !missing.contains(key) => key,
+                        _ => continue,
+                    };
+                    match self.resolve(attachment) {
+                        Some(name) => {
+                            resolution.found.insert(key.to_owned(), name);
+                        }
+                        None => {
+                            missing.insert(key);
+                            resolution.missing.push(MissingAttachment {
+                                conversation_id: convo.id.clone(),
+                                event_id: event.id.clone(),
+                                url: key.to_owned(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        resolution
+    }
+
+    fn search(&self, mut filename: String, mangle: bool) -> Option<String> {
+        loop {
+            if mangle {
+                filename = filename.replace('+', " ").replace('?', "_");
+            }
+
+            if self.source.contains(&filename) {
+                return Some(filename);
+            }
+
+            if Path::new(&filename).extension().is_none() {
+                let with_extension = format!("{}.jpg", filename);
+                if self.source.contains(&with_extension) {
+                    return Some(with_extension);
+                }
+            }
+
+            // Try unwrapping another layer of urlencoding.
+            match urldecode(&filename) {
+                Some(decoded) if decoded != filename => filename = decoded,
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn local_url(attachment: &model::Attachment) -> Option<&str> {
+    match attachment {
+        model::Attachment::Photo(photo) | model::Attachment::Video(photo) => Some(&photo.url),
+        model::Attachment::Audio(audio) => Some(&audio.url),
+        _ => None,
+    }
+}
+
+/// What identifies an attachment in a `Resolution`.
+fn attachment_key(attachment: &model::Attachment) -> Option<&str> {
+    match attachment {
+        model::Attachment::File(file) => Some(&file.export_name),
+        attachment => local_url(attachment),
+    }
+}
+
+/// The outcome of `AttachmentResolver::resolve_archive`.
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// File names in the source, by attachment URL, or by export name for uploaded files.
+    pub found: HashMap<String, String>,
+    /// Attachments with no matching file, each listed once, where it was first encountered.
+    pub missing: Vec<MissingAttachment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingAttachment {
+    pub conversation_id: String,
+    pub event_id: String,
+    /// The attachment URL, or the export name of an uploaded file.
+    pub url: String,
+}
+
+/// Decode %-escapes. Returns None if any are malformed or the result isn't UTF-8.
+fn urldecode(s: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut skip = 0;
+    for (i, byte) in s.as_bytes().iter().cloned().enumerate() {
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+
+        if byte == b'%' {
+            let num_str = s.get(i + 1 .. i + 3)?;
+            bytes.push(u8::from_str_radix(num_str, 16).ok()?);
+            skip = 2;
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
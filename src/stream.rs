@@ -0,0 +1,467 @@
+//! Conversation-at-a-time parsing of Hangouts.json.
+//!
+//! The top-level object is walked by hand to find the `conversations` array, and each element of
+//! it is then handed to serde on its own, so only one conversation is in memory at any time. The
+//! legacy layout's `conversation_state` array is recognized too, and converted as it is read.
+//!
+//! Other members of the top-level object are skipped, or with the "lenient" feature, kept.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Range;
+
+use serde::de::{Deserialize, Error as _};
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+use crate::raw;
+use crate::Error;
+
+pub struct ConversationStream<R> {
+    reader: R,
+    state: State,
+    layout: Layout,
+    index: usize,
+    /// Unknown members of the top-level object.
+    extra: raw::Extra,
+    /// Where the bytes of a value being kept are collected while it's walked over.
+    capture: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    FirstElement,
+    NextElement,
+    Done,
+}
+
+/// Which version of the Hangouts.json layout is being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layout {
+    Current,
+    Legacy,
+}
+
+impl Layout {
+    /// The top-level key holding the conversations.
+    fn key(self) -> &'static str {
+        match self {
+            Layout::Current => "conversations",
+            Layout::Legacy => "conversation_state",
+        }
+    }
+
+    /// The path from a conversation to its events.
+    fn events_path(self) -> &'static [&'static str] {
+        match self {
+            Layout::Current => &["events"],
+            Layout::Legacy => &["conversation_state", "event"],
+        }
+    }
+
+    /// JSON pointer from a conversation to its ID.
+    pub(crate) fn id_pointer(self) -> &'static str {
+        match self {
+            Layout::Current => "/conversation/conversation_id/id",
+            Layout::Legacy => "/conversation_id/id",
+        }
+    }
+}
+
+impl<R: BufRead> ConversationStream<R> {
+    pub fn new(reader: R) -> Self {
+        ConversationStream {
+            reader,
+            state: State::Start,
+            layout: Layout::Current,
+            index: 0,
+            extra: raw::Extra::new(),
+            capture: None,
+        }
+    }
+
+    /// Unknown members of the top-level object, which are kept with the "lenient" feature. Those
+    /// after the array of conversations are only read once the stream has ended.
+    #[cfg(feature = "lenient")]
+    pub fn extra(&self) -> &raw::Extra {
+        &self.extra
+    }
+
+    pub(crate) fn into_extra(self) -> raw::Extra {
+        self.extra
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Some(ref mut capture) = self.capture {
+            if let Ok(buf) = self.reader.fill_buf() {
+                capture.extend_from_slice(&buf[..amt]);
+            }
+        }
+        self.reader.consume(amt);
+    }
+
+    /// Skip whitespace and return the next byte without consuming it.
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(i) => {
+                    let b = buf[i];
+                    self.consume(i);
+                    return Ok(Some(b));
+                }
+                None if buf.is_empty() => return Ok(None),
+                None => {
+                    let len = buf.len();
+                    self.consume(len);
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), serde_json::Error> {
+        match self.peek().map_err(serde_json::Error::io)? {
+            Some(b) if b == expected => {
+                self.consume(1);
+                Ok(())
+            }
+            Some(b) => Err(serde_json::Error::custom(format!(
+                "expected {:?}, found {:?}", expected as char, b as char))),
+            None => Err(serde_json::Error::custom(format!(
+                "expected {:?}, found end of input", expected as char))),
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let b = self.reader.fill_buf()?.first().cloned();
+        if b.is_some() {
+            self.consume(1);
+        }
+        Ok(b)
+    }
+
+    /// Skip over a JSON value without interpreting it.
+    fn skip_value(&mut self) -> Result<(), serde_json::Error> {
+        let eof = || serde_json::Error::custom("unexpected end of input");
+        let mut depth = 0usize;
+        loop {
+            let b = self.peek().map_err(serde_json::Error::io)?.ok_or_else(eof)?;
+            match b {
+                b'{' | b'[' => {
+                    self.consume(1);
+                    depth += 1;
+                }
+                b'}' | b']' => {
+                    self.consume(1);
+                    depth = depth.checked_sub(1)
+                        .ok_or_else(|| serde_json::Error::custom("unbalanced brackets"))?;
+                }
+                b',' | b':' => self.consume(1),
+                b'"' => {
+                    self.consume(1);
+                    loop {
+                        match self.next_byte().map_err(serde_json::Error::io)?.ok_or_else(eof)? {
+                            b'\\' => { self.next_byte().map_err(serde_json::Error::io)?; }
+                            b'"' => break,
+                            _ => (),
+                        }
+                    }
+                }
+                _ => {
+                    // Scalar: consume up to the next delimiter.
+                    while let Some(&b) = self.reader.fill_buf().map_err(serde_json::Error::io)?
+                        .first()
+                    {
+                        if b.is_ascii_whitespace() || b == b',' || b == b'}' || b == b']' {
+                            break;
+                        }
+                        self.consume(1);
+                    }
+                }
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Attach the current position to an error from walking the document structure.
+    fn error(&self, e: serde_json::Error) -> Error {
+        if e.is_io() {
+            return Error::Io(e.into());
+        }
+        let path = match self.state {
+            State::Start => ".".to_owned(),
+            State::FirstElement | State::NextElement => {
+                format!("{}[{}]", self.layout.key(), self.index)
+            }
+            State::Done => ".".to_owned(),
+        };
+        Error::Json { path, conversation_id: None, source: e }
+    }
+
+    /// Walk the top-level object up to the opening bracket of the array of conversations, in
+    /// either layout. Returns None if the object has no such member.
+    fn find_conversations(&mut self) -> Result<Option<Layout>, serde_json::Error> {
+        self.expect(b'{')?;
+        let mut first = true;
+        loop {
+            if self.peek().map_err(serde_json::Error::io)? == Some(b'}') {
+                return Ok(None);
+            }
+            if !first {
+                self.expect(b',')?;
+            }
+            first = false;
+            let key = String::deserialize(&mut serde_json::Deserializer::from_reader(&mut self.reader))?;
+            self.expect(b':')?;
+            let layout = match key.as_str() {
+                "conversations" => Layout::Current,
+                "conversation_state" => Layout::Legacy,
+                _ => {
+                    self.unknown_member(key)?;
+                    continue;
+                }
+            };
+            self.expect(b'[')?;
+            return Ok(Some(layout));
+        }
+    }
+
+    /// Walk the rest of the top-level object, after the array of conversations if it had one.
+    fn finish(&mut self) -> Result<(), serde_json::Error> {
+        while self.peek().map_err(serde_json::Error::io)? != Some(b'}') {
+            self.expect(b',')?;
+            let key = String::deserialize(&mut serde_json::Deserializer::from_reader(&mut self.reader))?;
+            self.expect(b':')?;
+            self.unknown_member(key)?;
+        }
+        self.consume(1);
+        if self.layout == Layout::Legacy {
+            // The only other member of the legacy layout, which the current one has no place for.
+            self.extra.remove("continuation_end_timestamp");
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "lenient")]
+    fn unknown_member(&mut self, key: String) -> Result<(), serde_json::Error> {
+        // Values are captured as they're skipped, since serde_json would read past the end of
+        // numbers and lose the byte after them.
+        self.capture = Some(vec![]);
+        let result = self.skip_value();
+        let json = self.capture.take().unwrap_or_default();
+        result?;
+        self.extra.insert(key, serde_json::from_slice(&json)?);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "lenient"))]
+    fn unknown_member(&mut self, _key: String) -> Result<(), serde_json::Error> {
+        self.skip_value()
+    }
+
+    fn next_conversation(&mut self) -> Result<Option<raw::Conversation>, Error> {
+        match self.next_json()? {
+            Some((index, json)) => {
+                parse_conversation(self.layout, index, json.get().as_bytes()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Read the next conversation's JSON without parsing it, along with its index in the array.
+    pub(crate) fn next_json(&mut self) -> Result<Option<(usize, Box<RawValue>)>, Error> {
+        if self.state == State::Start {
+            match self.find_conversations().map_err(|e| self.error(e))? {
+                Some(layout) => {
+                    self.layout = layout;
+                    self.state = State::FirstElement;
+                }
+                None => {
+                    self.state = State::Done;
+                    self.finish().map_err(|e| self.error(e))?;
+                }
+            }
+        }
+
+        if self.state == State::Done {
+            return Ok(None);
+        }
+
+        if self.peek()? == Some(b']') {
+            self.consume(1);
+            self.state = State::Done;
+            self.finish().map_err(|e| self.error(e))?;
+            return Ok(None);
+        }
+
+        if self.state == State::NextElement {
+            self.expect(b',').map_err(|e| self.error(e))?;
+        }
+        self.state = State::NextElement;
+
+        let json = <Box<RawValue>>::deserialize(
+                &mut serde_json::Deserializer::from_reader(&mut self.reader))
+            .map_err(|e| self.error(e))?;
+        let index = self.index;
+        self.index += 1;
+        Ok(Some((index, json)))
+    }
+
+    pub(crate) fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.reader
+    }
+}
+
+impl ConversationStream<&[u8]> {
+    /// Walk the whole document, recording where each conversation starts and ends.
+    fn ranges(mut self) -> Result<(Layout, Vec<Range<usize>>, raw::Extra), Error> {
+        let len = self.reader.len();
+        let mut ranges = vec![];
+        if let Some(layout) = self.find_conversations().map_err(|e| self.error(e))? {
+            self.layout = layout;
+            self.state = State::FirstElement;
+            while self.peek()? != Some(b']') {
+                if self.state == State::NextElement {
+                    self.expect(b',').map_err(|e| self.error(e))?;
+                    self.peek()?;
+                }
+                self.state = State::NextElement;
+                let start = len - self.reader.len();
+                self.skip_value().map_err(|e| self.error(e))?;
+                ranges.push(start..len - self.reader.len());
+                self.index += 1;
+            }
+            self.consume(1);
+        }
+        self.state = State::Done;
+        self.finish().map_err(|e| self.error(e))?;
+        Ok((self.layout, ranges, self.extra))
+    }
+}
+
+impl<R: BufRead> Iterator for ConversationStream<R> {
+    type Item = Result<raw::Conversation, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_conversation() {
+            Ok(convo) => convo.map(Ok),
+            Err(e) => {
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl raw::Hangouts {
+    /// Parse conversations one at a time, without ever holding the whole archive in memory.
+    pub fn stream_conversations<R: Read>(reader: R) -> ConversationStream<BufReader<R>> {
+        ConversationStream::new(BufReader::new(reader))
+    }
+}
+
+/// Find the byte range of each conversation in a whole Hangouts.json, without parsing them. Only
+/// the structure of the JSON is checked, so each range can still fail to parse as a conversation.
+pub fn conversation_ranges(json: &[u8]) -> Result<Vec<Range<usize>>, Error> {
+    scan(json).map(|(_, ranges, _)| ranges)
+}
+
+/// The layout, the ranges of the conversations, and any unknown top-level members kept.
+pub(crate) fn scan(json: &[u8]) -> Result<(Layout, Vec<Range<usize>>, raw::Extra), Error> {
+    ConversationStream::new(json).ranges()
+}
+
+fn append_path(path: &mut String, segments: &serde_path_to_error::Path) {
+    for segment in segments {
+        if !matches!(segment, serde_path_to_error::Segment::Seq { .. }) && !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(&segment.to_string());
+    }
+}
+
+/// Parse one element of the array of conversations, which is at the given index.
+pub(crate) fn parse_conversation(layout: Layout, index: usize, json: &[u8])
+    -> Result<raw::Conversation, Error>
+{
+    let mut de = serde_json::Deserializer::from_slice(json);
+    let result = match layout {
+        Layout::Current => serde_path_to_error::deserialize(&mut de),
+        Layout::Legacy => serde_path_to_error::deserialize::<_, raw::legacy::ConversationEntry>(&mut de)
+            .map(raw::Conversation::from),
+    };
+    let e = match result {
+        Ok(convo) => return Ok(convo),
+        Err(e) => e,
+    };
+
+    let mut path = format!("{}[{}]", layout.key(), index);
+    append_path(&mut path, e.path());
+
+    let value: Option<Value> = serde_json::from_slice(json).ok();
+    let conversation_id = value.as_ref()
+        .and_then(|v| v.pointer(layout.id_pointer()))
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+
+    // Errors inside an event stop at the event itself, because serde can't track paths through
+    // `#[serde(flatten)]`. Re-parse the event's pieces separately to find where it went wrong.
+    let events_path = layout.events_path();
+    let mut segments = e.path().iter();
+    let in_events = events_path.iter().all(|name| matches!(segments.next(),
+        Some(serde_path_to_error::Segment::Map { key }) if key == name));
+    let refined = match (in_events, segments.next(), segments.next(), value) {
+        (true, Some(serde_path_to_error::Segment::Seq { index }), None, Some(mut value)) => {
+            value.pointer_mut(&format!("/{}/{}", events_path.join("/"), index))
+                .map(Value::take)
+                .and_then(refine_event_error)
+        }
+        _ => None,
+    };
+
+    let source = match refined {
+        Some(inner) => {
+            append_path(&mut path, inner.path());
+            inner.into_inner()
+        }
+        None => e.into_inner(),
+    };
+
+    Err(Error::Json { path, conversation_id, source })
+}
+
+fn refine_event_error(event: Value)
+    -> Option<serde_path_to_error::Error<serde_json::Error>>
+{
+    let mut header = match event {
+        Value::Object(map) => map,
+        _ => return None,
+    };
+    let mut data = serde_json::Map::new();
+    for key in raw::EVENT_DATA_KEYS {
+        if let Some(v) = header.remove(*key) {
+            data.insert((*key).to_owned(), v);
+        }
+    }
+    header.remove("event_type");
+
+    if let Err(e) = serde_path_to_error::deserialize::<_, raw::EventHeader>(Value::Object(header)) {
+        return Some(e);
+    }
+
+    for (key, v) in data {
+        let mut variant = serde_json::Map::new();
+        variant.insert(key, v);
+        if let Err(e) = serde_path_to_error::deserialize::<_, raw::EventData>(Value::Object(variant)) {
+            return Some(e);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,121 @@
+//! Reading Hangouts.json and its attachments straight out of the archives Takeout produces.
+//!
+//! Large exports are split into several archives (`takeout-...-001.zip`, `-002.zip`, ...), each
+//! of which is complete on its own, with Hangouts.json in one of them and attachments spread
+//! across all of them. Zip archives need the `zip` feature and tarballs the `tar` feature.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::raw::Hangouts;
+use crate::resolver::{AttachmentResolver, Directory, FileSource, TAKEOUT_PREFIX};
+use crate::stream::ConversationStream;
+use crate::Error;
+
+const JSON_NAME: &str = "Hangouts.json";
+
+/// A set of Takeout archives, treated as one.
+pub struct Takeout {
+    parts: Vec<Box<dyn FileSource>>,
+    json_part: usize,
+}
+
+impl Hangouts {
+    /// Open the Takeout archives at the given paths, which can be zip files, tarballs (gzipped
+    /// or not), or already-extracted Takeout folders, in any order.
+    pub fn open_takeout<I>(paths: I) -> Result<Takeout, Error>
+        where I: IntoIterator,
+              I::Item: AsRef<Path>,
+    {
+        let parts = paths.into_iter()
+            .map(|path| open_part(path.as_ref()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let json_part = parts.iter()
+            .position(|part| part.contains(JSON_NAME))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+                format!("none of the archives contain {}{}", TAKEOUT_PREFIX, JSON_NAME)))?;
+        Ok(Takeout { parts, json_part })
+    }
+}
+
+impl Takeout {
+    /// Read Hangouts.json without extracting it.
+    pub fn json_reader(&self) -> io::Result<Box<dyn Read + '_>> {
+        self.parts[self.json_part].reader(JSON_NAME)
+    }
+
+    /// Parse the whole of Hangouts.json.
+    pub fn hangouts(&self) -> Result<Hangouts, Error> {
+        Hangouts::from_reader(self.json_reader()?)
+    }
+
+    /// Parse Hangouts.json one conversation at a time.
+    pub fn stream_conversations(&self)
+        -> Result<ConversationStream<BufReader<Box<dyn Read + '_>>>, Error>
+    {
+        Ok(Hangouts::stream_conversations(self.json_reader()?))
+    }
+
+    /// A resolver which looks for attachments in all the archives.
+    pub fn resolver(&self) -> AttachmentResolver<&Self> {
+        AttachmentResolver::new(self)
+    }
+
+    fn part(&self, name: &str) -> io::Result<&dyn FileSource> {
+        self.parts.iter()
+            .find(|part| part.contains(name))
+            .map(|part| &**part)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no file named {:?}", name)))
+    }
+}
+
+/// The files in the Hangouts folder of every archive. If a name appears in more than one, the
+/// first one given to `open_takeout` wins.
+impl FileSource for Takeout {
+    fn contains(&self, name: &str) -> bool {
+        self.parts.iter().any(|part| part.contains(name))
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        self.part(name)?.read(name)
+    }
+
+    fn reader(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        self.part(name)?.reader(name)
+    }
+}
+
+fn open_part(path: &Path) -> io::Result<Box<dyn FileSource>> {
+    if path.is_dir() {
+        let hangouts = path.join(TAKEOUT_PREFIX);
+        let dir = if hangouts.is_dir() { hangouts } else { path.to_owned() };
+        return Ok(Box::new(Directory::open(dir)?));
+    }
+
+    let mut magic = Vec::with_capacity(262);
+    File::open(path)?.take(262).read_to_end(&mut magic)?;
+
+    if magic.starts_with(b"PK\x03\x04") {
+        #[cfg(feature = "zip")]
+        return Ok(Box::new(crate::resolver::ZipSource::open(path, TAKEOUT_PREFIX)?));
+        #[cfg(not(feature = "zip"))]
+        return Err(unsupported(path, "zip"));
+    }
+
+    if magic.starts_with(&[0x1f, 0x8b]) || magic.get(257..262) == Some(b"ustar") {
+        #[cfg(feature = "tar")]
+        return Ok(Box::new(crate::resolver::TarSource::open(path, TAKEOUT_PREFIX)?));
+        #[cfg(not(feature = "tar"))]
+        return Err(unsupported(path, "tar"));
+    }
+
+    Err(io::Error::new(io::ErrorKind::InvalidData,
+        format!("{:?} is not a zip file or tarball", path)))
+}
+
+#[cfg(not(all(feature = "zip", feature = "tar")))]
+fn unsupported(path: &Path, feature: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported,
+        format!("{:?} can't be read without the `{}` feature", path, feature))
+}
@@ -0,0 +1,172 @@
+//! Getting the text of a message, for searching or other processing.
+//!
+//! `Event::text` takes a `TextOptions` saying what to do with the parts of a message that aren't
+//! plain text: links, attachments, `/me` annotations, and line breaks. The result only allocates
+//! when it has to; the text of a message with a single segment is borrowed as-is.
+
+use std::borrow::Cow;
+
+use crate::model::{self, Attachment, Message};
+use crate::raw;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextOptions {
+    links: LinkStyle,
+    attachment_placeholder: Option<String>,
+    annotations: AnnotationStyle,
+    newlines: Newlines,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// The link's text, as it was shown in the message.
+    #[default]
+    Text,
+    /// The URL the link went to.
+    Url,
+    /// The text, followed by the URL in parentheses if it's different.
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnnotationStyle {
+    #[default]
+    Ignore,
+    /// Put `/me ` in front of action messages, the way they were typed.
+    MeCommand,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newlines {
+    /// Line breaks are kept as they were sent, which may include `\r\n`.
+    #[default]
+    Keep,
+    /// Every kind of line break becomes `\n`.
+    Normalize,
+    /// Each run of line breaks becomes a single space, putting the whole message on one line.
+    Spaces,
+}
+
+impl TextOptions {
+    pub fn new() -> Self {
+        TextOptions::default()
+    }
+
+    pub fn links(mut self, style: LinkStyle) -> Self {
+        self.links = style;
+        self
+    }
+
+    /// Text to put in place of each attachment, after the rest of the message. `{type}` in it is
+    /// replaced with the kind of attachment, e.g. `"[{type}]"` gives `"[photo]"`. Attachments are
+    /// left out if this isn't set.
+    pub fn attachment_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.attachment_placeholder = Some(placeholder.into());
+        self
+    }
+
+    pub fn annotations(mut self, style: AnnotationStyle) -> Self {
+        self.annotations = style;
+        self
+    }
+
+    pub fn newlines(mut self, newlines: Newlines) -> Self {
+        self.newlines = newlines;
+        self
+    }
+}
+
+impl raw::Event {
+    /// The text of a chat message, as specified by the options, or `None` if this isn't a message.
+    pub fn text(&self, options: &TextOptions) -> Option<Cow<'_, str>> {
+        match self.data {
+            raw::EventData::ChatMessage { ref message_content, ref annotation, .. } => {
+                let attachments = message_content.attachments.iter()
+                    .map(|a| embed_item_type(&a.embed_item));
+                let annotations = annotation.as_deref().unwrap_or_default();
+                Some(text(&message_content.segments, attachments, annotations, options))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Message {
+    /// The text of the message, as specified by the options.
+    pub fn text_with(&self, options: &TextOptions) -> Cow<'_, str> {
+        let attachments = self.attachments.iter().map(Attachment::media_type);
+        text(&self.segments, attachments, &self.annotations, options)
+    }
+}
+
+/// Like `Attachment::media_type`, without needing to convert the embed item.
+fn embed_item_type(item: &raw::EmbedItem) -> &str {
+    if let Some(ref photo) = item.plus_photo {
+        photo.media_type.as_str()
+    } else if item.plus_audio_v2.is_some() {
+        "AUDIO"
+    } else if item.place_v2.is_some() {
+        "PLACE"
+    } else if item.thing_v2.is_some() {
+        "THING"
+    } else {
+        item.types.first().map(String::as_str).unwrap_or("UNKNOWN")
+    }
+}
+
+fn text<'a>(
+    segments: &'a [raw::ChatSegment],
+    attachment_types: impl Iterator<Item = &'a str>,
+    annotations: &[raw::Annotation],
+    options: &TextOptions,
+) -> Cow<'a, str> {
+    let mut pieces: Vec<Cow<'a, str>> = vec![];
+    if options.annotations == AnnotationStyle::MeCommand && model::is_action(annotations) {
+        pieces.push(Cow::Borrowed("/me "));
+    }
+
+    for segment in segments {
+        match segment {
+            raw::ChatSegment::Text { text, .. } => pieces.push(Cow::Borrowed(text)),
+            raw::ChatSegment::Link { text, link_data, .. } => {
+                let url = &link_data.link_target;
+                pieces.push(match options.links {
+                    LinkStyle::Text => Cow::Borrowed(text),
+                    LinkStyle::Url => Cow::Borrowed(url),
+                    LinkStyle::Both if text == url => Cow::Borrowed(text),
+                    LinkStyle::Both => Cow::Owned(format!("{} ({})", text, url)),
+                });
+            }
+            raw::ChatSegment::LineBreak { text, .. } => {
+                pieces.push(Cow::Borrowed(text.as_deref().unwrap_or("\n")));
+            }
+            raw::ChatSegment::Unknown(_) => (),
+        }
+    }
+
+    if let Some(ref placeholder) = options.attachment_placeholder {
+        for typ in attachment_types {
+            if !pieces.is_empty() {
+                pieces.push(Cow::Borrowed(" "));
+            }
+            pieces.push(Cow::Owned(placeholder.replace("{type}", &typ.to_lowercase())));
+        }
+    }
+
+    let combined = match pieces.len() {
+        0 => Cow::Borrowed(""),
+        1 => pieces.pop().unwrap(),
+        _ => Cow::Owned(pieces.concat()),
+    };
+    match options.newlines {
+        Newlines::Keep => combined,
+        Newlines::Normalize if !combined.contains('\r') => combined,
+        Newlines::Normalize => Cow::Owned(combined.replace("\r\n", "\n").replace('\r', "\n")),
+        Newlines::Spaces if !combined.contains(['\r', '\n']) => combined,
+        Newlines::Spaces => {
+            Cow::Owned(combined.split(['\r', '\n']).filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "))
+        }
+    }
+}
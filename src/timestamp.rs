@@ -0,0 +1,231 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::Error;
+
+/// A point in time, as microseconds since the Unix epoch.
+///
+/// Hangouts.json stores these as strings of digits; they deserialize directly from that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn from_micros(micros: i64) -> Self {
+        Timestamp(micros)
+    }
+
+    pub fn as_micros(self) -> i64 {
+        self.0
+    }
+
+    /// Whole seconds since the epoch, and the nanoseconds past that.
+    pub fn parts(self) -> (i64, u32) {
+        let secs = self.0.div_euclid(1_000_000);
+        let micros = self.0.rem_euclid(1_000_000) as u32;
+        (secs, micros * 1_000)
+    }
+
+    /// A UTC date and time of day, to the second. A leap second, 86400 seconds into the day, is
+    /// taken as the start of the next day. `None` if there's no such date or time, or it's too far
+    /// from the epoch to be represented.
+    pub fn from_utc(year: i64, month: u32, day: u32, secs_of_day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month)
+            || secs_of_day > 86_400
+        {
+            return None;
+        }
+        days_from_civil(year, month, day)?
+            .checked_mul(86_400)?
+            .checked_add(i64::from(secs_of_day))?
+            .checked_mul(1_000_000)
+            .map(Timestamp)
+    }
+
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    fn out_of_range(self) -> Error {
+        Error::Timestamp { value: self.0.to_string() }
+    }
+
+    /// The date and time of day, in a time zone `utc_offset` seconds ahead of UTC.
+    pub(crate) fn civil(self, utc_offset: i32) -> Civil {
+        let secs = self.parts().0 + i64::from(utc_offset);
+        let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let time = secs.rem_euclid(86_400) as u32;
+        Civil { year, month, day, hour: time / 3600, minute: time / 60 % 60, second: time % 60 }
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        let offset = Duration::from_micros(self.0.unsigned_abs());
+        if self.0 >= 0 {
+            UNIX_EPOCH + offset
+        } else {
+            UNIX_EPOCH - offset
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Civil {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl Civil {
+    pub fn date(&self) -> (i64, u32, u32) {
+        (self.year, self.month, self.day)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(ts: Timestamp) -> Self {
+        ts.to_system_time()
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        s.parse()
+            .map(Timestamp)
+            .map_err(|_| Error::Timestamp { value: s.to_owned() })
+    }
+}
+
+/// Formats as RFC 3339 in UTC, e.g. `2017-07-14T02:40:00.000000Z`.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (secs, nsecs) = self.parts();
+        let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let time = secs.rem_euclid(86_400);
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            year, month, day, time / 3600, time / 60 % 60, time % 60, nsecs / 1_000)
+    }
+}
+
+/// Convert days since the epoch to a (year, month, day) date in the proleptic Gregorian calendar.
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// The inverse of `civil_from_days`, or `None` if the result would overflow.
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let year = if month <= 2 { year.checked_sub(1)? } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era.checked_mul(146_097)?.checked_add(doe - 719_468)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimestampVisitor;
+
+        impl Visitor<'_> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of microseconds since the epoch")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Timestamp, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<Timestamp, E> {
+                Ok(Timestamp(n))
+            }
+
+            fn visit_u64<E: de::Error>(self, n: u64) -> Result<Timestamp, E> {
+                i64::try_from(n)
+                    .map(Timestamp)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(n), &self))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(TimestampVisitor)
+        } else {
+            deserializer.deserialize_i64(TimestampVisitor)
+        }
+    }
+}
+
+/// Serializes as a string of digits, the same as in Hangouts.json, for human-readable formats.
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&self.0)
+        } else {
+            serializer.serialize_i64(self.0)
+        }
+    }
+}
+
+/// Fails with `Error::Timestamp` if the timestamp is outside of chrono's range.
+#[cfg(feature = "chrono")]
+impl TryFrom<Timestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = Error;
+
+    fn try_from(ts: Timestamp) -> Result<Self, Error> {
+        let (secs, nsecs) = ts.parts();
+        chrono::TimeZone::timestamp_opt(&chrono::Utc, secs, nsecs)
+            .single()
+            .ok_or_else(|| ts.out_of_range())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(dt: chrono::DateTime<chrono::Utc>) -> Self {
+        Timestamp(dt.timestamp() * 1_000_000 + i64::from(dt.timestamp_subsec_micros()))
+    }
+}
+
+/// Fails with `Error::Timestamp` if the timestamp is outside of the time crate's range.
+#[cfg(feature = "time")]
+impl TryFrom<Timestamp> for time::OffsetDateTime {
+    type Error = Error;
+
+    fn try_from(ts: Timestamp) -> Result<Self, Error> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(i128::from(ts.0) * 1_000)
+            .map_err(|_| ts.out_of_range())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Timestamp {
+    fn from(dt: time::OffsetDateTime) -> Self {
+        Timestamp((dt.unix_timestamp_nanos() / 1_000) as i64)
+    }
+}
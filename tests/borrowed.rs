@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+
+use hangouts_json_parser::raw::{self, borrowed};
+use hangouts_json_parser::{Error, Hangouts};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn borrowed_matches_owned() {
+    let owned: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let parsed = borrowed::Hangouts::from_slice(SAMPLE.as_bytes()).unwrap();
+
+    let convo = &parsed.conversations[0];
+    assert!(matches!(convo.header.conversation_id.id, Cow::Borrowed("c1")));
+    let texts = parsed.conversations.iter()
+        .flat_map(|convo| &convo.events)
+        .filter_map(|event| match event.data {
+            borrowed::EventData::ChatMessage { ref message_content, .. } => Some(&message_content.segments),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|segment| match segment {
+            borrowed::ChatSegment::Text { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // With "lenient", events go through `raw::Event`, so only the conversation headers borrow.
+    if !cfg!(feature = "lenient") {
+        assert!(texts.iter().any(|text| matches!(text, Cow::Borrowed("hi alice"))));
+    }
+
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(&owned).unwrap());
+    let converted = raw::Hangouts::from(parsed);
+    assert_eq!(converted, owned);
+    assert_eq!(borrowed::Hangouts::from(converted), borrowed::Hangouts::from_slice(SAMPLE.as_bytes()).unwrap());
+}
+
+#[test]
+fn borrowed_error_path() {
+    let json = SAMPLE.replacen("\"sender_id\": {", "\"sender_id\": 5, \"x\": {", 1);
+    match borrowed::Hangouts::from_slice(json.as_bytes()) {
+        Err(Error::Json { path, conversation_id, .. }) => {
+            assert!(path.starts_with("conversations[0].events"), "{}", path);
+            assert_eq!(conversation_id.as_deref(), Some("c1"));
+        }
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    // Not valid JSON at all, so there's no ID to find.
+    let json = SAMPLE.replacen("\"sender_id\": {", "\"sender_id\": [{", 1);
+    match borrowed::Hangouts::from_slice(json.as_bytes()) {
+        Err(Error::Json { conversation_id, .. }) => assert_eq!(conversation_id, None),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
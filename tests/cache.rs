@@ -0,0 +1,83 @@
+#![cfg(feature = "cache")]
+
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime};
+
+use hangouts_json_parser::cache::cache_path;
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn save_and_load() {
+    let dir = std::env::temp_dir().join(format!("hangouts-cache-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("Hangouts.json");
+    fs::write(&source, SAMPLE).unwrap();
+    let set_modified = |secs| File::options().write(true).open(&source).unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    set_modified(1_500_000_000);
+
+    assert!(Hangouts::load_cache(&source).unwrap().is_none());
+    let parsed = Hangouts::from_path_cached(&source).unwrap();
+    assert!(cache_path(&source).is_file());
+    assert_eq!(Hangouts::load_cache(&source).unwrap().as_ref(), Some(&parsed));
+
+    // Touched, but the same contents.
+    set_modified(1_600_000_000);
+    assert_eq!(Hangouts::load_cache(&source).unwrap().as_ref(), Some(&parsed));
+
+    // Changed, but the same size.
+    fs::write(&source, SAMPLE.replacen("hi alice", "hi ALICE", 1)).unwrap();
+    assert!(Hangouts::load_cache(&source).unwrap().is_none());
+
+    fs::write(cache_path(&source), b"not a cache").unwrap();
+    assert!(Hangouts::load_cache(&source).unwrap().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn features_must_match() {
+    let dir = std::env::temp_dir().join(format!("hangouts-cache-features-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("Hangouts.json");
+    fs::write(&source, SAMPLE).unwrap();
+    let parsed = Hangouts::from_path(&source).unwrap();
+    parsed.save_cache(&source).unwrap();
+
+    // Pretend the cache was written by a build with or without each feature that this one
+    // wasn't, by flipping the flag in the header.
+    let cache = fs::read(cache_path(&source)).unwrap();
+    for name in ["lenient", "deny_unknown_fields", "strict"] {
+        let mut key = vec![0xa0 | name.len() as u8];
+        key.extend_from_slice(name.as_bytes());
+        let at = cache.windows(key.len()).position(|w| w == &key[..]).unwrap() + key.len();
+        let mut changed = cache.clone();
+        changed[at] ^= 1; // MessagePack's false and true are 0xc2 and 0xc3.
+        fs::write(cache_path(&source), &changed).unwrap();
+        assert!(Hangouts::load_cache(&source).unwrap().is_none(), "{}", name);
+    }
+
+    fs::write(cache_path(&source), &cache).unwrap();
+    assert_eq!(Hangouts::load_cache(&source).unwrap(), Some(parsed));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_save_leaves_no_temporary_file() {
+    let dir = std::env::temp_dir().join(format!("hangouts-cache-fail-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("Hangouts.json");
+    fs::write(&source, SAMPLE).unwrap();
+
+    // A directory where the cache should go can't be replaced by it.
+    fs::create_dir_all(cache_path(&source).join("in the way")).unwrap();
+    let parsed = Hangouts::from_path(&source).unwrap();
+    assert!(parsed.save_cache(&source).is_err());
+    let mut temp = cache_path(&source).into_os_string();
+    temp.push(".tmp");
+    assert!(!std::path::Path::new(&temp).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::io::Read;
+
+use hangouts_json_parser::compression::{decompress, Compression};
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+/// Check that the data decompresses back to the sample, and parses from a file.
+fn round_trip(name: &str, compressed: &[u8]) {
+    let mut json = String::new();
+    decompress(compressed).unwrap().read_to_string(&mut json).unwrap();
+    assert_eq!(json, SAMPLE);
+
+    let dir = std::env::temp_dir().join(format!("hangouts-compression-{}-{}", name,
+        std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Hangouts.json");
+    fs::write(&path, compressed).unwrap();
+    let expected: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    assert_eq!(Hangouts::from_path(&path).unwrap(), expected);
+    let streamed = Hangouts::stream_path(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(streamed, expected.conversations);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn detect() {
+    assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+    assert_eq!(Compression::detect(b"BZh91AY"), Compression::Bzip2);
+    assert_eq!(Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]), Compression::Zstd);
+    assert_eq!(Compression::detect(b"\xfd7zXZ\x00\x00"), Compression::Xz);
+    assert_eq!(Compression::detect(SAMPLE.as_bytes()), Compression::None);
+    assert_eq!(Compression::detect(b""), Compression::None);
+    assert_eq!(Compression::detect(&[0x1f]), Compression::None);
+}
+
+#[test]
+fn uncompressed() {
+    round_trip("none", SAMPLE.as_bytes());
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn missing_feature() {
+    match decompress(&[0x1f, 0x8b, 0x08, 0x00][..]) {
+        Err(e) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+            assert!(e.to_string().contains("`gzip` feature"), "{}", e);
+        }
+        Ok(_) => panic!("gzip input decompressed without the gzip feature"),
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip() {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(SAMPLE.as_bytes()).unwrap();
+    round_trip("gzip", &encoder.finish().unwrap());
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2() {
+    use std::io::Write;
+    let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+    encoder.write_all(SAMPLE.as_bytes()).unwrap();
+    round_trip("bzip2", &encoder.finish().unwrap());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd() {
+    round_trip("zstd", &zstd::encode_all(SAMPLE.as_bytes(), 0).unwrap());
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz() {
+    use std::io::Write;
+    let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+    encoder.write_all(SAMPLE.as_bytes()).unwrap();
+    round_trip("xz", &encoder.finish().unwrap());
+}
@@ -0,0 +1,38 @@
+use std::convert::TryFrom;
+
+use hangouts_json_parser::export::html::Exporter;
+use hangouts_json_parser::model::{Archive, Attachment};
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn html_conversation() {
+    let hangouts: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+
+    let exporter = Exporter::new().attachment_urls(|attachment| match attachment {
+        Attachment::Photo(photo) if photo.photo_id == "p1" => Some("files/cat pic.jpg".to_owned()),
+        _ => None,
+    });
+    let html = exporter.conversation(archive.conversation("c1").unwrap());
+    assert!(html.starts_with("<!DOCTYPE html>"), "{}", html);
+    assert!(html.contains("<title>Me, Alice</title>"), "{}", html);
+    assert!(html.contains("<h2 class=\"day\">2017-07-14</h2>"), "{}", html);
+    assert!(html.contains("<b><i>see &lt;this&gt;</i></b><br>"), "{}", html);
+    let link = "<s><u><a href=\"http://example.com\">example.com</a></u></s>";
+    assert!(html.contains(link), "{}", html);
+    assert!(!html.contains("<ul>"), "{}", html);
+    assert!(html.contains("* <span class=\"sender\""), "{}", html);
+    assert!(html.contains("<img src=\"files/cat pic.jpg\""), "{}", html);
+
+    let html = exporter.conversation(archive.conversation("c2").unwrap());
+    assert!(html.contains("named the conversation &ldquo;Friends&rdquo;"), "{}", html);
+    assert!(html.contains(">Alice</span> left</div>"), "{}", html);
+    assert!(html.contains("hello all &amp; co"), "{}", html);
+
+    // In UTC+10 the first message is on the next day.
+    let exporter = Exporter::new().utc_offset(10 * 3600);
+    let html = exporter.conversation(archive.conversation("c2").unwrap());
+    assert!(html.contains("<h2 class=\"day\">2017-11-07</h2>"), "{}", html);
+}
@@ -0,0 +1,123 @@
+use std::convert::TryFrom;
+use std::fs;
+
+use hangouts_json_parser::google_chat;
+use hangouts_json_parser::model::{Archive, Attachment, EventKind};
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+const DM_INFO: &str = r#"{
+  "members": [
+    { "name": "Me", "email": "me@example.com", "user_type": "Human" },
+    { "name": "Alice", "email": "alice@example.com", "user_type": "Human" }
+  ]
+}"#;
+
+const DM_MESSAGES: &str = r#"{
+  "messages": [
+    {
+      "creator": { "name": "Me", "email": "me@example.com", "user_type": "Human" },
+      "created_date": "Friday, July 14, 2017 at 2:40:00 AM UTC",
+      "text": "hi alice",
+      "topic_id": "t1",
+      "message_id": "dm/t1/m1"
+    },
+    {
+      "creator": { "name": "Alice", "email": "alice@example.com", "user_type": "Human" },
+      "created_date": "Tuesday, March 1, 2022 at 12:05:09\u202fPM UTC",
+      "text": "moved to chat\nsee attached",
+      "topic_id": "t2",
+      "message_id": "dm/t2/m2",
+      "attached_files": [ { "original_name": "cat.jpg", "export_name": "File-cat.jpg" } ]
+    }
+  ]
+}"#;
+
+const SPACE_INFO: &str = r#"{ "name": "New space", "members": [ { "name": "Carol" } ] }"#;
+
+const SPACE_MESSAGES: &str = r#"{
+  "messages": [
+    {
+      "creator": { "name": "Carol" },
+      "created_date": "Saturday, January 1, 2022 at 12:00:00 AM UTC",
+      "text": "first"
+    }
+  ]
+}"#;
+
+#[test]
+fn merge_migrated_groups() {
+    let root = std::env::temp_dir().join(format!("hangouts-chat-{}", std::process::id()));
+    for (id, info, messages) in &[("DM abc", DM_INFO, DM_MESSAGES), ("Space xyz", SPACE_INFO, SPACE_MESSAGES)] {
+        let dir = root.join("Groups").join(id);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("group_info.json"), info).unwrap();
+        fs::write(dir.join("messages.json"), messages).unwrap();
+    }
+    let groups = google_chat::read_dir(&root).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    let mut archive = Archive::try_from(serde_json::from_str::<Hangouts>(SAMPLE).unwrap()).unwrap();
+    let before = archive.conversation("c1").unwrap().events.len();
+    let count = archive.conversations.len();
+    archive.merge_google_chat(groups);
+
+    assert_eq!(archive.conversations.len(), count + 1);
+    let merged = archive.conversation("c1").unwrap();
+    assert_eq!(merged.events.len(), before + 1);
+
+    let last = merged.events.last().unwrap();
+    assert_eq!(last.id, "dm/t2/m2");
+    assert_eq!(last.timestamp.to_string(), "2022-03-01T12:05:09.000000Z");
+    let alice = merged.participants.iter().find(|p| p.display_name() == "Alice").unwrap();
+    assert_eq!(last.sender, *alice);
+    match last.kind {
+        EventKind::Message(ref msg) => {
+            assert_eq!(msg.text(), "moved to chat\nsee attached");
+            assert!(matches!(msg.attachments[..], [Attachment::File(ref f)] if f.export_name == "File-cat.jpg"));
+        }
+        ref other => panic!("unexpected event: {:?}", other),
+    }
+
+    let space = archive.conversation("Space xyz").unwrap();
+    assert_eq!(space.display_name(), "New space");
+    assert_eq!(space.events[0].sender.display_name(), "Carol");
+}
+
+#[test]
+fn bad_date() {
+    for date in ["Someday at noon", "Friday, July 99, 2017 at 2:40:00 AM UTC",
+        "Friday, February 29, 2017 at 2:40:00 AM UTC", "Friday, July 0, 2017 at 2:40:00 AM UTC",
+        "Friday, July 14, 300000000 at 2:40:00 AM UTC",
+        "Friday, July 14, -9223372036854775808 at 2:40:00 AM UTC",
+        "Friday, July 14, 2017 at 13:40:00 AM UTC", "Friday, July 14, 2017 at 0:40:00 PM UTC"]
+    {
+        let messages = format!(r#"{{ "messages": [ {{ "created_date": "{}", "text": "x" }} ] }}"#,
+            date);
+        let err = google_chat::Group::from_readers("DM a", &b"{}"[..], messages.as_bytes())
+            .unwrap_err();
+        assert!(err.to_string().contains("messages.json: messages[0].created_date"), "{}", err);
+    }
+}
+
+#[test]
+fn merge_group_with_a_different_member() {
+    let info = r#"{ "members": [ { "name": "Me" }, { "name": "Alice" }, { "name": "Robert" } ] }"#;
+    let messages = r#"{ "messages": [
+        { "creator": { "name": "Robert" }, "text": "hello all & co",
+          "created_date": "Friday, March 2, 2018 at 2:15:00 PM UTC" },
+        { "creator": { "name": "Alice" }, "text": "new",
+          "created_date": "Saturday, March 3, 2018 at 2:15:00 PM UTC" }
+    ] }"#;
+    let group = google_chat::Group::from_readers("Space g", info.as_bytes(), messages.as_bytes())
+        .unwrap();
+
+    let mut archive = Archive::try_from(serde_json::from_str::<Hangouts>(SAMPLE).unwrap()).unwrap();
+    let before = archive.conversation("c2").unwrap().events.len();
+    let count = archive.conversations.len();
+    archive.merge_google_chat(vec![group]);
+
+    assert_eq!(archive.conversations.len(), count);
+    assert_eq!(archive.conversation("c2").unwrap().events.len(), before + 1);
+}
@@ -0,0 +1,126 @@
+use std::convert::TryFrom;
+
+use hangouts_json_parser::google_voice::{normalize_phone_number, Voice};
+use hangouts_json_parser::model::{Archive, Attachment, CallKind, EventKind};
+use hangouts_json_parser::Hangouts;
+use serde_json::{json, Value};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+const TEXTS: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE html>
+<html><head><title>Alice</title></head><body>
+<div class="hChatLog hfeed">
+<div class="message"><abbr class="dt" title="2017-07-13T22:40:00.000-04:00">Jul 13, 2017</abbr>:
+<cite class="sender vcard"><a class="tel" href="tel:+15550001111"><abbr class="fn" title="">Me</abbr></a></cite>:
+<q>hi alice</q>
+</div>
+<div class="message"><abbr class="dt" title="2017-07-14T02:41:30.500Z">Jul 14, 2017</abbr>:
+<cite class="sender vcard"><a class="tel" href="tel:+15551234567"><span class="fn">Alice</span></a></cite>:
+<q>fish &amp; chips?<br>at 6</q>
+<div><img src="Alice - Text - 2017-07-14T02_40_00Z-1-1.jpg" alt="Image MMS Attachment" /></div>
+</div>
+</div>
+</body></html>
+"#;
+
+const MISSED: &str = r#"<html><body>
+<div class="haudio">
+<span class="fn">Missed call from</span>
+<div class="contributor vcard">Missed call from <a class="tel" href="tel:(555) 987-6543"><span class="fn">Bob</span></a></div>
+<abbr class="published" title="2018-01-02T03:04:05.000Z">Jan 2, 2018</abbr>
+</div>
+</body></html>
+"#;
+
+const VOICEMAIL: &str = r#"<html><body>
+<div class="haudio">
+<div class="contributor vcard">Voicemail from <a class="tel" href="tel:+15559876543"><span class="fn">Bob</span></a></div>
+<abbr class="published" title="2018-01-02T03:05:00.000Z">Jan 2, 2018</abbr>
+<span class="description"><span class="full-text">Call me back</span></span>
+<abbr class="duration" title="PT1M23S">(00:01:23)</abbr>
+</div>
+</body></html>
+"#;
+
+#[test]
+fn normalize() {
+    assert_eq!(normalize_phone_number("tel:+1 555-123-4567").as_deref(), Some("+15551234567"));
+    assert_eq!(normalize_phone_number("(555) 123-4567").as_deref(), Some("+15551234567"));
+    assert_eq!(normalize_phone_number("15551234567").as_deref(), Some("+15551234567"));
+    assert_eq!(normalize_phone_number("+44 20 7946 0958").as_deref(), Some("+442079460958"));
+    assert_eq!(normalize_phone_number("22395").as_deref(), Some("22395"));
+    assert_eq!(normalize_phone_number("Alice"), None);
+}
+
+#[test]
+fn merge_voice() {
+    let mut voice = Voice::default();
+    voice.add_file("Alice - Text - 2017-07-14T02_40_00Z.html", TEXTS).unwrap();
+    voice.add_file("Bob - Missed - 2018-01-02T03_04_05Z.html", MISSED).unwrap();
+    voice.add_file("Bob - Voicemail - 2018-01-02T03_05_00Z.html", VOICEMAIL).unwrap();
+    voice.add_file("Bob - Unknown - 2018-01-02T03_05_00Z.html", "").unwrap();
+    assert_eq!(voice.conversations.keys().collect::<Vec<_>>(), ["+15551234567", "+15559876543"]);
+
+    let mut sample: Value = serde_json::from_str(SAMPLE).unwrap();
+    sample["conversations"][0]["conversation"]["conversation"]["participant_data"][1]["phone_number"] =
+        json!({ "e164": "+15551234567" });
+    let hangouts: Hangouts = serde_json::from_value(sample).unwrap();
+    let mut archive = Archive::try_from(hangouts).unwrap();
+    let before = archive.conversation("c1").unwrap().events.len();
+    let count = archive.conversations.len();
+    archive.merge_google_voice(voice);
+
+    assert_eq!(archive.conversations.len(), count + 1);
+    let merged = archive.conversation("c1").unwrap();
+    assert_eq!(merged.events.len(), before + 1);
+    let last = merged.events.iter().find(|e| e.id.ends_with("#1")).unwrap();
+    assert_eq!(last.timestamp.to_string(), "2017-07-14T02:41:30.500000Z");
+    assert_eq!(last.sender.display_name(), "Alice");
+    match last.kind {
+        EventKind::Message(ref msg) => {
+            assert_eq!(msg.text(), "fish & chips?\nat 6");
+            assert!(matches!(msg.attachments[..], [Attachment::File(_)]));
+        }
+        ref other => panic!("unexpected event: {:?}", other),
+    }
+
+    let bob = archive.conversation("+15559876543").unwrap();
+    assert_eq!(bob.display_name(), "Bob");
+    assert!(matches!(bob.events[0].kind, EventKind::Call(ref call) if call.kind == CallKind::Missed));
+    match bob.events[1].kind {
+        EventKind::Call(ref call) => assert_eq!(call.kind, CallKind::Voicemail {
+            duration: std::time::Duration::from_secs(83),
+            transcript: Some("Call me back".to_owned()),
+        }),
+        ref other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn bad_timestamp() {
+    for timestamp in ["yesterday", "2017-07-13T22:40:00.12345é-04:00",
+        "2017-07-13T22:40:00.1x-04:00", "2017-07-13T22:40:00-99999999999999999:00",
+        "2017-07-13T22:40:00+25:00", "2017-07-13T22:40:00-04:60", "4294967295-07-13T22:40:00Z",
+        "2017-02-29T22:40:00Z"]
+    {
+        let html = format!(r#"<div class="message"><abbr class="dt" title="{}">x</abbr><q>hi</q></div>"#,
+            timestamp);
+        let err = Voice::default().add_file("+15551234567 - Text - x.html", &html).unwrap_err();
+        assert!(err.to_string().contains("+15551234567 - Text - x.html"), "{}", err);
+    }
+}
+
+#[test]
+fn oversized_duration() {
+    for duration in ["PT99999999999999999H", "PT5124095576030431H16S"] {
+        let html = VOICEMAIL.replace("PT1M23S", duration);
+        let mut voice = Voice::default();
+        voice.add_file("Bob - Voicemail - 2018-01-02T03_05_00Z.html", &html).unwrap();
+        match voice.conversations["+15559876543"].events[0].kind {
+            EventKind::Call(ref call) => assert!(matches!(call.kind,
+                CallKind::Voicemail { duration, .. } if duration.as_secs() == 0), "{}", duration),
+            ref other => panic!("unexpected event: {:?}", other),
+        }
+    }
+}
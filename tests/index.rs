@@ -0,0 +1,57 @@
+use std::fs;
+
+use hangouts_json_parser::index::{index_path, Index};
+use hangouts_json_parser::raw::ConversationType;
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn read_one_conversation() {
+    let dir = std::env::temp_dir().join(format!("hangouts-index-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("Hangouts.json");
+    fs::write(&source, SAMPLE).unwrap();
+    let expected: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+
+    let index = Index::open(&source).unwrap();
+    assert!(index_path(&source).is_file());
+    let loaded = Index::load(&source).unwrap().unwrap();
+    assert_eq!(loaded, index);
+    assert_eq!(loaded.get("c1").map(|e| e.index), Some(0));
+    assert_eq!(index.conversations().len(), expected.conversations.len());
+
+    let groups = index.of_kind(&ConversationType::Group).map(|e| e.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(groups, ["c2"]);
+    let with_bob = index.with_participant("bob").map(|e| e.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(with_bob, ["c2"]);
+    assert_eq!(index.get("c2").unwrap().name.as_deref(), Some("Friends"));
+
+    for convo in &expected.conversations {
+        let id = &convo.header.conversation_id.id;
+        assert_eq!(index.conversation(id).unwrap().as_ref(), Some(convo));
+    }
+    assert!(index.conversation("nope").unwrap().is_none());
+
+    fs::write(&source, format!("{}\n", SAMPLE)).unwrap();
+    assert!(Index::load(&source).unwrap().is_none());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_save_leaves_no_temporary_file() {
+    let dir = std::env::temp_dir().join(format!("hangouts-index-fail-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("Hangouts.json");
+    fs::write(&source, SAMPLE).unwrap();
+
+    // A directory where the index should go can't be replaced by it.
+    fs::create_dir_all(index_path(&source).join("in the way")).unwrap();
+    let index = Index::build(&source).unwrap();
+    assert!(index.save().is_err());
+    let mut temp = index_path(&source).into_os_string();
+    temp.push(".tmp");
+    assert!(!std::path::Path::new(&temp).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
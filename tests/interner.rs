@@ -0,0 +1,32 @@
+use hangouts_json_parser::interner::Interner;
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn intern_while_streaming() {
+    let hangouts: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let mut interner = Interner::new();
+    let handles = Hangouts::stream_conversations(SAMPLE.as_bytes())
+        .interned(&mut interner)
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<_>>();
+
+    assert_eq!(handles.len(), hangouts.conversations.len());
+    for (handle, convo) in handles.iter().zip(&hangouts.conversations) {
+        assert_eq!(interner.conversation(*handle), &convo.header.conversation_id);
+        assert_eq!(interner.conversation_handle(&convo.header.conversation_id.id), Some(*handle));
+    }
+
+    // Alice is in both conversations, but only has the one handle.
+    let participants = hangouts.interned_participants(&mut interner);
+    assert_eq!(participants.len(), hangouts.participants().len());
+    for (handle, data) in &participants {
+        assert_eq!(interner.participant(*handle), &data.id);
+        assert_eq!(interner.participant_handle(&data.id), Some(*handle));
+    }
+    for event in hangouts.conversations.iter().flat_map(|convo| &convo.events) {
+        assert!(interner.participant_handle(&event.header.sender_id).is_some());
+    }
+    assert_eq!(hangouts.interner().participants().len(), interner.participants().len());
+}
@@ -0,0 +1,50 @@
+use hangouts_json_parser::raw::legacy;
+use hangouts_json_parser::{Error, Hangouts};
+use serde_json::{json, Value};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+/// Rearrange the sample into the pre-2019 layout.
+fn legacy_sample() -> Value {
+    let current: Value = serde_json::from_str(SAMPLE).unwrap();
+    let states = current["conversations"].as_array().unwrap().iter()
+        .map(|convo| json!({
+            "conversation_id": convo["conversation"]["conversation_id"],
+            "conversation_state": {
+                "conversation_id": convo["conversation"]["conversation_id"],
+                "conversation": convo["conversation"]["conversation"],
+                "event": convo["events"],
+                "event_continuation_token": { "event_timestamp": "1500000000000000" },
+            },
+            "response_header": { "status": "OK" },
+        }))
+        .collect::<Vec<_>>();
+    json!({
+        "continuation_end_timestamp": "1500000000000000",
+        "conversation_state": states,
+    })
+}
+
+#[test]
+fn legacy_layout_is_detected() {
+    let expected: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let legacy = serde_json::to_vec(&legacy_sample()).unwrap();
+    assert_eq!(Hangouts::from_reader(&legacy[..]).unwrap(), expected);
+
+    let parsed: legacy::Hangouts = serde_json::from_slice(&legacy).unwrap();
+    assert_eq!(Hangouts::from(parsed), expected);
+}
+
+#[test]
+fn legacy_error_paths() {
+    let mut legacy = legacy_sample();
+    legacy["conversation_state"][0]["conversation_state"]["event"][0]["sender_id"] = json!(5);
+    let json = serde_json::to_vec(&legacy).unwrap();
+    match Hangouts::from_reader(&json[..]) {
+        Err(Error::Json { path, conversation_id, .. }) => {
+            assert_eq!(path, "conversation_state[0].conversation_state.event[0].sender_id");
+            assert!(conversation_id.is_some());
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
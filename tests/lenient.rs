@@ -0,0 +1,93 @@
+#![cfg(feature = "lenient")]
+
+use hangouts_json_parser::raw::EventData;
+use hangouts_json_parser::Hangouts;
+use serde_json::{json, Value};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+/// The sample with unknown data of every kind in it.
+fn unknown_sample() -> Value {
+    let mut json: Value = serde_json::from_str(SAMPLE).unwrap();
+    // Object keys are sorted, so these end up before and after the conversations.
+    json["aaa_before"] = json!({ "n": 1 });
+    json["zzz_after"] = json!(5);
+
+    let events = &mut json["conversations"][0]["events"];
+    events[0]["zz"] = json!(true);
+    events[0]["chat_message"]["message_content"]["segment"][0]["zz"] = json!("x");
+    events[1]["chat_message"]["message_content"]["segment"][0] =
+        json!({ "type": "HOLOGRAM", "text": "hi" });
+    events[4]["hangout_event"]["event_type"] = json!("TELEPORT");
+    let event = events[2].as_object_mut().unwrap();
+    event.remove("chat_message");
+    event.insert("future_event".to_owned(), json!({ "a": 1 }));
+    json["conversations"][1]["conversation"]["conversation"]["zz"] = json!([]);
+    json
+}
+
+#[test]
+fn unknown_data_is_reported() {
+    let json = serde_json::to_vec(&unknown_sample()).unwrap();
+    let expected = [
+        ("aaa_before", 1),
+        ("zzz_after", 1),
+        ("conversations[].conversation.conversation.zz", 1),
+        ("conversations[].events[]", 1),
+        ("conversations[].events[].zz", 1),
+        ("conversations[].events[].chat_message.message_content.segment[]", 1),
+        ("conversations[].events[].chat_message.message_content.segment[].zz", 1),
+        ("conversations[].events[].hangout_event", 1),
+    ];
+    let expected = expected.iter().map(|&(path, n)| (path.to_owned(), n)).collect();
+
+    let hangouts = Hangouts::from_reader(&json[..]).unwrap();
+    assert_eq!(hangouts.unknown_data().summary(), expected);
+    assert!(matches!(hangouts.conversations[0].events[2].data, EventData::Unknown(_)));
+
+    let parsed: Hangouts = serde_json::from_slice(&json).unwrap();
+    assert_eq!(parsed.unknown_data().summary(), expected);
+    assert_eq!(parsed, hangouts);
+
+    #[cfg(feature = "rayon")]
+    assert_eq!(Hangouts::from_slice_parallel(&json).unwrap(), hangouts);
+}
+
+#[test]
+fn unknown_top_level_members_while_streaming() {
+    let json = br#"{ "a": [1, "x\"]"], "b": -1.5e2, "conversations": [], "c": {"d": null} }"#;
+    let mut stream = Hangouts::stream_conversations(&json[..]);
+    assert!(stream.next().is_none());
+    assert_eq!(Value::Object(stream.extra().clone()),
+        json!({ "a": [1, "x\"]"], "b": -1.5e2, "c": { "d": null } }));
+}
+
+#[test]
+fn unknown_fields_in_delivery_mediums_and_phone_numbers() {
+    let mut json: Value = serde_json::from_str(SAMPLE).unwrap();
+    let convo = &mut json["conversations"][1];
+    let event = &mut convo["events"][2];
+    event["delivery_medium"]["zz"] = json!(1);
+    event["delivery_medium"]["self_phone"]["zz"] = json!(2);
+    let state = &mut convo["conversation"]["conversation"]["self_conversation_state"];
+    state["delivery_medium_option"][1]["zz"] = json!(3);
+    state["delivery_medium_option"][1]["delivery_medium"]["zz"] = json!(4);
+    let bob = &mut convo["conversation"]["conversation"]["participant_data"][2];
+    bob["phone_number"]["zz"] = json!(5);
+    bob["phone_number"]["i18n_data"]["zz"] = json!(6);
+
+    let hangouts: Hangouts = serde_json::from_value(json).unwrap();
+    let options = "conversations[].conversation.conversation.self_conversation_state\
+        .delivery_medium_option[]";
+    let phone = "conversations[].conversation.conversation.participant_data[].phone_number";
+    let expected = [
+        "conversations[].events[].delivery_medium.zz".to_owned(),
+        "conversations[].events[].delivery_medium.self_phone.zz".to_owned(),
+        format!("{}.zz", options),
+        format!("{}.delivery_medium.zz", options),
+        format!("{}.zz", phone),
+        format!("{}.i18n_data.zz", phone),
+    ];
+    assert_eq!(hangouts.unknown_data().summary(),
+        expected.iter().map(|path| (path.clone(), 1)).collect());
+}
@@ -0,0 +1,140 @@
+use std::convert::TryFrom;
+
+use hangouts_json_parser::model::{Archive, Attachment, CallKind, EventKind, Medium};
+use hangouts_json_parser::raw::{EmbedItem, MediumType};
+use hangouts_json_parser::{Error, Hangouts, Timestamp};
+use serde_json::{json, Value};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn delivery_medium() {
+    let hangouts: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let state = &hangouts.conversations[1].header.details.self_conversation_state;
+    let options = state.delivery_medium_option.as_ref().unwrap();
+    assert_eq!(options[1].delivery_medium.medium_type, MediumType::GoogleVoice);
+    assert_eq!(options[1].primary, Some(true));
+
+    let archive = Archive::try_from(hangouts).unwrap();
+    let bob = archive.participants.values().find(|p| p.display_name() == "Bob").unwrap();
+    assert_eq!(bob.phone_number.as_deref(), Some("+15555550123"));
+
+    let events = &archive.conversation("c2").unwrap().events;
+    let sms = events.iter().find(|e| e.id == "f3").unwrap();
+    assert!(sms.is_sms());
+    assert_eq!(sms.medium, Medium::GoogleVoice { self_phone: Some("+15555550100".to_owned()) });
+    assert!(events.iter().filter(|e| e.id != "f3").all(|e| e.medium == Medium::Hangouts));
+}
+
+/// The sample with three of the five attachments of event e4 broken: a photo whose types don't
+/// list it, audio with a fractional duration, and a thing without its field.
+fn bad_attachments() -> Value {
+    let mut json: Value = serde_json::from_str(SAMPLE).unwrap();
+    let attachments = &mut json["conversations"][0]["events"][3]["chat_message"]
+        ["message_content"]["attachment"];
+    attachments[0]["embed_item"]["type"] = json!(["PLUS_AUDIO_V2"]);
+    attachments[2]["embed_item"]["plus_audio_v2"]["duration"] = json!("12.5");
+    attachments[4]["embed_item"].as_object_mut().unwrap().remove("thing_v2");
+    json
+}
+
+fn validation_message(result: Result<Attachment, Error>) -> String {
+    match result {
+        Err(Error::Validation { message, .. }) => message,
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_embed_items() {
+    let json = bad_attachments();
+    let item = |i: usize| -> EmbedItem {
+        serde_json::from_value(json["conversations"][0]["events"][3]["chat_message"]
+            ["message_content"]["attachment"][i]["embed_item"].clone()).unwrap()
+    };
+    assert_eq!(validation_message(Attachment::try_from(item(0))),
+        r#"embed item with types ["PLUS_AUDIO_V2"] has an unexpected plus_photo field"#);
+    assert_eq!(validation_message(Attachment::try_from(item(2))),
+        r#"invalid audio duration "12.5" in embed item"#);
+    assert_eq!(validation_message(Attachment::try_from(item(4))),
+        r#"embed item with types ["THING_V2"] is missing the thing_v2 field"#);
+
+    let (attachment, problem) = Attachment::from_embed_item(item(2));
+    assert_eq!(attachment, Attachment::Unknown(Box::new(item(2))));
+    assert!(problem.is_some());
+    let (attachment, problem) = Attachment::from_embed_item(item(1));
+    assert!(matches!(attachment, Attachment::Video(_)));
+    assert!(problem.is_none());
+}
+
+#[test]
+fn bad_attachments_are_kept_as_unknown() {
+    let hangouts: Hangouts = serde_json::from_value(bad_attachments()).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+
+    let event = archive.conversation("c1").unwrap().events.iter().find(|e| e.id == "e4").unwrap();
+    let attachments = match event.kind {
+        EventKind::Message(ref msg) => &msg.attachments,
+        ref other => panic!("unexpected event: {:?}", other),
+    };
+    let kinds = attachments.iter()
+        .map(|a| match a {
+            Attachment::Unknown(_) => "unknown",
+            _ => "ok",
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["unknown", "ok", "unknown", "ok", "unknown"]);
+
+    assert_eq!(archive.problems.len(), 3);
+    for problem in &archive.problems {
+        match problem {
+            Error::Validation { conversation_id, message } => {
+                assert_eq!(conversation_id.as_deref(), Some("c1"));
+                assert!(message.starts_with("event e4: "), "{}", message);
+            }
+            other => panic!("unexpected problem: {:?}", other),
+        }
+    }
+
+    let hangouts: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    assert!(Archive::try_from(hangouts).unwrap().problems.is_empty());
+}
+
+#[test]
+fn events_are_sorted_and_resolved() {
+    let mut json: Value = serde_json::from_str(SAMPLE).unwrap();
+    json["conversations"][0]["events"].as_array_mut().unwrap().reverse();
+    let hangouts: Hangouts = serde_json::from_value(json).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+
+    let events = &archive.conversation("c1").unwrap().events;
+    let ids = events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids, ["e1", "e2", "e3", "e4", "e5", "e6"]);
+    let senders = events.iter().map(|e| e.sender.display_name()).collect::<Vec<_>>();
+    assert_eq!(senders, ["Me", "Alice", "Alice", "Me", "Me", "Me"]);
+    assert_eq!(events[0].timestamp, Timestamp::from_micros(1_500_000_000_000_000));
+    assert_eq!(Some(events[1].timestamp),
+        Timestamp::from_utc(2017, 7, 14, 2 * 3600 + 41 * 60 + 40));
+}
+
+#[test]
+fn bad_call_duration_is_a_problem() {
+    let mut json: Value = serde_json::from_str(SAMPLE).unwrap();
+    json["conversations"][0]["events"][5]["hangout_event"]["hangout_duration_secs"] =
+        json!("forever");
+    let hangouts: Hangouts = serde_json::from_value(json).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+
+    let event = archive.conversation("c1").unwrap().events.iter().find(|e| e.id == "e6").unwrap();
+    match event.kind {
+        EventKind::Call(ref call) => assert_eq!(call.kind, CallKind::Unknown(json!("forever"))),
+        ref other => panic!("unexpected event: {:?}", other),
+    }
+    match &archive.problems[..] {
+        [Error::Validation { conversation_id, message }] => {
+            assert_eq!(conversation_id.as_deref(), Some("c1"));
+            assert_eq!(message, r#"event e6: invalid call duration "forever""#);
+        }
+        other => panic!("unexpected problems: {:?}", other),
+    }
+}
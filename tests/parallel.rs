@@ -0,0 +1,31 @@
+use hangouts_json_parser::stream::conversation_ranges;
+use serde_json::Value;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn ranges() {
+    let ranges = conversation_ranges(SAMPLE.as_bytes()).unwrap();
+    let expected: Value = serde_json::from_str(SAMPLE).unwrap();
+    assert_eq!(ranges.len(), expected["conversations"].as_array().unwrap().len());
+    for (range, convo) in ranges.into_iter().zip(expected["conversations"].as_array().unwrap()) {
+        assert_eq!(serde_json::from_str::<Value>(&SAMPLE[range]).unwrap(), *convo);
+    }
+
+    assert_eq!(conversation_ranges(br#"{ "other": [1, "]"], "conversations": [ ] }"#).unwrap(), vec![]);
+    assert!(conversation_ranges(br#"{ "conversations": [ {"a": [} ] }"#).is_err());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_matches_sequential() {
+    use hangouts_json_parser::Hangouts;
+
+    let expected = Hangouts::from_reader(SAMPLE.as_bytes()).unwrap();
+    assert_eq!(Hangouts::from_slice_parallel(SAMPLE.as_bytes()).unwrap(), expected);
+
+    let broken = SAMPLE.replacen("\"sender_id\": {", "\"sender_id\": 5, \"x\": {", 2);
+    let sequential = Hangouts::from_reader(broken.as_bytes()).unwrap_err().to_string();
+    let parallel = Hangouts::from_slice_parallel(broken.as_bytes()).unwrap_err().to_string();
+    assert_eq!(parallel, sequential);
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use hangouts_json_parser::model::{Attachment, Audio, Image, Message, Photo};
+use hangouts_json_parser::raw::PhotoMediaType;
+use hangouts_json_parser::render::{file_url, Ansi, Html, Markdown, PlainText, Renderer};
+use serde_json::json;
+
+fn message() -> Message {
+    let segments = json!([
+        { "type": "TEXT", "text": "see ", "formatting": { "bold": true } },
+        { "type": "LINK", "text": "this <page>", "link_data": {
+            "link_target": "https://example.com/a_b", "display_url": "example.com/a_b" } },
+        { "type": "LINE_BREAK", "text": "\n" },
+        { "type": "TEXT", "text": "not *that*", "formatting": { "underline": true } },
+        { "type": "LINK", "text": "one", "link_data": { "link_target": "javascript:alert(1)" } },
+    ]);
+    Message {
+        segments: serde_json::from_value(segments).unwrap(),
+        attachments: vec![],
+        annotations: vec![],
+    }
+}
+
+#[test]
+fn renderers() {
+    let msg = message();
+    let body = msg.body();
+    assert_eq!(PlainText.render(&body), "see this <page>\nnot *that*one");
+    assert_eq!(Html.render(&body),
+        "<b>see </b><a href=\"https://example.com/a_b\">this &lt;page&gt;</a><br>\n\
+        <u>not *that*</u>one");
+    assert_eq!(Markdown.render(&body),
+        "**see** [this \\<page\\>](<https://example.com/a_b>)\\\n<u>not \\*that\\*</u>one");
+    assert_eq!(Ansi.render(&body),
+        "\x1b[1msee \x1b[0m\x1b[4;34mthis <page>\x1b[0m <example.com/a_b>\n\
+        \x1b[4mnot *that*\x1b[0m\x1b[4;34mone\x1b[0m <javascript:alert(1)>");
+}
+
+fn text(text: &str) -> Message {
+    Message {
+        segments: serde_json::from_value(json!([{ "type": "TEXT", "text": text }])).unwrap(),
+        attachments: vec![],
+        annotations: vec![],
+    }
+}
+
+#[test]
+fn markdown_line_starts() {
+    let render = |s: &str| Markdown.render(&text(s).body());
+    assert_eq!(render("# not a heading"), "\\# not a heading");
+    assert_eq!(render("hi\n> not a quote"), "hi\n\\> not a quote");
+    assert_eq!(render("- one\n  + two\n12. three\n1) four"),
+        "\\- one\n  \\+ two\n12\\. three\n1\\) four");
+    assert_eq!(render("title\n==="), "title\n\\===");
+    assert_eq!(render("a - b + c 1. d"), "a - b + c 1. d");
+}
+
+#[test]
+fn action() {
+    let mut msg = message();
+    msg.segments.truncate(1);
+    msg.annotations = serde_json::from_value(json!([{ "type": 4, "value": "" }])).unwrap();
+    assert!(msg.is_action());
+    let body = msg.body();
+    assert_eq!(PlainText.render_message("Alice", &body), "* Alice see ");
+    assert_eq!(Html.render_message("<Alice>", &body), "* <b>&lt;Alice&gt;</b> <b>see </b>");
+    assert_eq!(Markdown.render_message("Alice", &body), "\\* **Alice** **see** ");
+    assert_eq!(PlainText.render_message("Bob", &message().body()),
+        "Bob: see this <page>\nnot *that*one");
+}
+
+fn render(renderer: impl Renderer, attachment: &Attachment) -> String {
+    let mut out = String::new();
+    renderer.attachment(&mut out, attachment);
+    out
+}
+
+#[test]
+fn unsafe_attachment_urls() {
+    let photo = |url: &str, thumbnail: &str| Attachment::Photo(Photo {
+        photo_id: "p".to_owned(),
+        album_id: "a".to_owned(),
+        media_type: PhotoMediaType::Photo,
+        url: url.to_owned(),
+        original_content_url: None,
+        download_url: None,
+        thumbnail: Image { url: thumbnail.to_owned(), width: None, height: None },
+    });
+    let audio = Attachment::Audio(Audio {
+        photo_id: "p".to_owned(),
+        album_id: "a".to_owned(),
+        url: "javascript:alert(1)".to_owned(),
+        embed_url: "https://example.com/embed".to_owned(),
+        duration: Duration::from_secs(12),
+    });
+
+    assert_eq!(render(Markdown, &photo("https://x/p", "https://x/t")),
+        "[![photo](<https://x/t>)](<https://x/p>)");
+    assert_eq!(render(Markdown, &photo("javascript:alert(1)", "https://x/t")),
+        "\\[photo: javascript:alert(1)\\]");
+    assert_eq!(render(Markdown, &photo("https://x/p", " JavaScript:alert(1)")),
+        "[\\[photo\\]](<https://x/p>)");
+    assert_eq!(render(Markdown, &audio), "\\[audio: javascript:alert(1)\\]");
+    assert_eq!(render(Html, &photo("javascript:alert(1)", "https://x/t")),
+        "[photo: javascript:alert(1)]");
+    assert_eq!(render(Html, &audio), "[audio: javascript:alert(1)]");
+}
+
+#[test]
+#[cfg(unix)]
+fn file_urls() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    assert_eq!(file_url(Path::new("/tmp/cat pic#1.jpg")).as_deref(),
+        Some("file:///tmp/cat%20pic%231.jpg"));
+    assert_eq!(file_url(Path::new("/a/é?.png")).as_deref(), Some("file:///a/%C3%A9%3F.png"));
+    assert_eq!(file_url(Path::new(OsStr::from_bytes(b"/a/\xff"))), None);
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+
+use hangouts_json_parser::model::Archive;
+use hangouts_json_parser::resolver::{AttachmentResolver, Directory, FileSource};
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+struct Names(HashSet<&'static str>);
+
+impl FileSource for Names {
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains(name)
+    }
+
+    fn read(&self, _name: &str) -> io::Result<Vec<u8>> {
+        Ok(vec![])
+    }
+}
+
+#[test]
+fn resolve_url_variants() {
+    let resolver = AttachmentResolver::new(Names(
+        ["cat pic.jpg", "photo.jpg", "a b_c.png"].iter().cloned().collect()));
+    assert_eq!(resolver.resolve_url("https://x/cat%2520pic.jpg").as_deref(), Some("cat pic.jpg"));
+    assert_eq!(resolver.resolve_url("https://x/photo").as_deref(), Some("photo.jpg"));
+    assert_eq!(resolver.resolve_url("https://x/a+b%3Fc.png").as_deref(), Some("a b_c.png"));
+    assert_eq!(resolver.resolve_url("https://x/missing.jpg"), None);
+    assert_eq!(resolver.resolve_url("https://x/bad%zz"), None);
+}
+
+#[test]
+fn resolve_archive_from_directory() {
+    let dir = std::env::temp_dir().join(format!("hangouts-resolver-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("cat pic.jpg"), b"meow").unwrap();
+    fs::write(dir.join("video.jpg"), b"").unwrap();
+
+    let archive = Archive::try_from(serde_json::from_str::<Hangouts>(SAMPLE).unwrap()).unwrap();
+    let resolver = AttachmentResolver::new(Directory::open(&dir).unwrap());
+    let resolution = resolver.resolve_archive(&archive);
+    let photo_url = "https://lh3.googleusercontent.com/x/cat%2520pic.jpg";
+    assert_eq!(resolution.found[photo_url], "cat pic.jpg");
+    assert_eq!(resolution.found["https://ex/video"], "video.jpg");
+    assert_eq!(resolution.missing.iter().map(|m| m.url.as_str()).collect::<Vec<_>>(),
+        vec!["https://ex/audio"]);
+    assert_eq!(resolver.source().read("cat pic.jpg").unwrap(), b"meow");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_attachments_are_listed_once() {
+    let mut json: serde_json::Value = serde_json::from_str(SAMPLE).unwrap();
+    let audio = json["conversations"][0]["events"][3]["chat_message"]["message_content"]
+        ["attachment"][2].clone();
+    json["conversations"][1]["events"][2]["chat_message"]["message_content"]["attachment"] =
+        serde_json::json!([audio.clone(), audio]);
+    let archive = Archive::try_from(serde_json::from_value::<Hangouts>(json).unwrap()).unwrap();
+
+    let resolver = AttachmentResolver::new(Names(HashSet::new()));
+    let missing = resolver.resolve_archive(&archive).missing;
+    let missing = missing.iter()
+        .map(|m| (m.conversation_id.as_str(), m.event_id.as_str(), m.url.as_str()))
+        .collect::<Vec<_>>();
+    assert!(missing.contains(&("c1", "e4", "https://ex/audio")), "{:?}", missing);
+    assert_eq!(missing.iter().filter(|m| m.2 == "https://ex/audio").count(), 1);
+}
+
+#[test]
+fn open_extracted_takeout() {
+    let root = std::env::temp_dir().join(format!("hangouts-takeout-{}", std::process::id()));
+    let dir = root.join("Takeout/Hangouts");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("Hangouts.json"), SAMPLE).unwrap();
+    fs::write(dir.join("cat pic.jpg"), b"meow").unwrap();
+
+    let takeout = Hangouts::open_takeout(&[&root]).unwrap();
+    let hangouts = takeout.hangouts().unwrap();
+    assert_eq!(hangouts.conversations.len(), takeout.stream_conversations().unwrap().count());
+    let photo_url = "https://lh3.googleusercontent.com/x/cat%2520pic.jpg";
+    assert_eq!(takeout.resolver().resolve_url(photo_url).as_deref(), Some("cat pic.jpg"));
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,42 @@
+use hangouts_json_parser::raw::{
+    ConversationType, ConversationView, MediaType, MembershipChangeType, OtrStatus,
+};
+use hangouts_json_parser::Hangouts;
+use serde_json::Value;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn parse_serialize_parse() {
+    let parsed: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let serialized = serde_json::to_string(&parsed).unwrap();
+    let reparsed: Hangouts = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(parsed, reparsed);
+}
+
+#[test]
+fn serialize_preserves_json() {
+    let parsed: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let original: Value = serde_json::from_str(SAMPLE).unwrap();
+    assert_eq!(original, serde_json::to_value(&parsed).unwrap());
+}
+
+#[test]
+fn string_enums() {
+    fn parse<T: serde::de::DeserializeOwned>(s: &str) -> T {
+        serde_json::from_value(Value::String(s.to_owned())).unwrap()
+    }
+    assert_eq!(parse::<ConversationType>("STICKY_ONE_TO_ONE"), ConversationType::StickyOneToOne);
+    assert_eq!(parse::<OtrStatus>("OFF_THE_RECORD"), OtrStatus::OffTheRecord);
+    assert_eq!(parse::<ConversationView>("ARCHIVED_VIEW"), ConversationView::Archived);
+    assert_eq!(parse::<MembershipChangeType>("JOIN"), MembershipChangeType::Join);
+    assert_eq!(parse::<MembershipChangeType>("LEAVE"), MembershipChangeType::Leave);
+    assert_eq!(parse::<MediaType>("AUDIO_VIDEO"), MediaType::AudioVideo);
+
+    let other = parse::<ConversationType>("SPACE");
+    assert_eq!(other, ConversationType::Other("SPACE".to_owned()));
+    assert_eq!(serde_json::to_value(&other).unwrap(), Value::String("SPACE".to_owned()));
+    let borrowed: ConversationView = serde_json::from_str(r#""SPAM_VIEW""#).unwrap();
+    assert_eq!(borrowed, ConversationView::Other("SPAM_VIEW".to_owned()));
+    assert_eq!(serde_json::to_string(&borrowed).unwrap(), r#""SPAM_VIEW""#);
+}
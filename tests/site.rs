@@ -0,0 +1,82 @@
+use std::convert::TryFrom;
+use std::fs;
+
+use hangouts_json_parser::export::html::Exporter;
+use hangouts_json_parser::export::site::Site;
+use hangouts_json_parser::model::{Archive, Attachment};
+use hangouts_json_parser::Hangouts;
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+#[test]
+fn static_site() {
+    let hangouts: Hangouts = serde_json::from_str(SAMPLE).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+    let dir = std::env::temp_dir().join(format!("hangouts-site-{}", std::process::id()));
+
+    let exporter = Exporter::new().attachment_urls(|attachment| match attachment {
+        Attachment::Photo(photo) if photo.photo_id == "p1" => Some("files/cat.jpg".to_owned()),
+        _ => None,
+    });
+    Site::new(exporter).title("My <chats>").write(&archive, &dir).unwrap();
+    let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+
+    let index = read("index.html");
+    assert!(index.contains("<title>My &lt;chats&gt;</title>"), "{}", index);
+    // Most recent first.
+    let c1 = index.find("conversations/c1/index.html").unwrap();
+    let c2 = index.find("conversations/c2/index.html").unwrap();
+    assert!(c2 < c1, "{}", index);
+    assert!(index.contains("2017-11-06&nbsp;&ndash; 2018-03-02"), "{}", index);
+
+    let months = read("conversations/c2/index.html");
+    let november = "<a href=\"2017-11.html\">November 2017</a> (0 messages)";
+    assert!(months.contains(november), "{}", months);
+    assert!(months.contains("<a href=\"2018-03.html\">March 2018</a> (1 message)"), "{}", months);
+    let march = read("conversations/c2/2018-03.html");
+    assert!(march.contains("<a href=\"2017-11.html\">&larr; November 2017</a>"), "{}", march);
+    assert!(march.contains("hello all &amp; co"), "{}", march);
+    let july = read("conversations/c1/2017-07.html");
+    assert!(july.contains("<img src=\"../../files/cat.jpg\""), "{}", july);
+
+    let participants = fs::read_dir(dir.join("participants")).unwrap().count();
+    assert_eq!(participants, 3);
+    assert!(index.contains("<a href=\"participants/"), "{}", index);
+
+    let search = read("search.js");
+    let json = search.strip_prefix("var SEARCH = ").unwrap().trim_end().trim_end_matches(';');
+    let search: serde_json::Value = serde_json::from_str(json).unwrap();
+    let docs = &search["terms"]["alice"];
+    assert_eq!(docs.as_array().unwrap().len(), 1);
+    let doc = &search["docs"][docs[0].as_u64().unwrap() as usize];
+    assert_eq!(doc[1], "conversations/c1/2017-07.html#e1");
+    assert_eq!(doc[4], "hi alice");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn colliding_slugs() {
+    let mut json: serde_json::Value = serde_json::from_str(SAMPLE).unwrap();
+    let copy = json["conversations"][0].clone();
+    json["conversations"].as_array_mut().unwrap().push(copy);
+    // "a.b" becomes "a_b", so "a_b" has to be numbered, and then "A_b-2" too, since file names
+    // might not be case-sensitive.
+    for (i, id) in ["a_b", "a.b", "A_b-2"].iter().enumerate() {
+        let convo = &mut json["conversations"][i]["conversation"];
+        convo["conversation_id"]["id"] = serde_json::json!(id);
+        convo["conversation"]["id"]["id"] = serde_json::json!(id);
+    }
+    let hangouts: Hangouts = serde_json::from_value(json).unwrap();
+    let archive = Archive::try_from(hangouts).unwrap();
+    let dir = std::env::temp_dir().join(format!("hangouts-site-slugs-{}", std::process::id()));
+
+    Site::new(Exporter::new()).write(&archive, &dir).unwrap();
+    let mut slugs = fs::read_dir(dir.join("conversations")).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    slugs.sort();
+    assert_eq!(slugs, ["A_b-2-2", "a_b", "a_b-2"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use hangouts_json_parser::stream::conversation_ranges;
+use hangouts_json_parser::{Error, Hangouts};
+use serde_json::{json, Value};
+
+const SAMPLE: &str = include_str!("data/Hangouts.json");
+
+fn ids(json: &[u8]) -> Vec<String> {
+    Hangouts::stream_conversations(json)
+        .map(|convo| convo.unwrap().header.details.id.id)
+        .collect()
+}
+
+#[test]
+fn unknown_keys_around_conversations() {
+    let sample: Value = serde_json::from_str(SAMPLE).unwrap();
+    let conversations = serde_json::to_string(&sample["conversations"]).unwrap();
+    // Strings with escaped quotes and brackets in them, which mustn't end the skipped values
+    // early, and scalars of every kind.
+    let json = format!(r#"{{
+        "before": {{ "a": "x\"}}]{{[", "b": ["\\", "}}", "\\\"]"], "c": [[], {{}}] }},
+        "scalars": [1.5e3, -2, true, false, null, ""],
+        "conversations": {},
+        "after": {{ "d": "\"}}" }}
+    }}"#, conversations);
+    assert_eq!(ids(json.as_bytes()), ["c1", "c2"]);
+
+    let ranges = conversation_ranges(json.as_bytes()).unwrap();
+    assert_eq!(ranges.len(), 2);
+    let first: Value = serde_json::from_slice(&json.as_bytes()[ranges[0].clone()]).unwrap();
+    assert_eq!(first, sample["conversations"][0]);
+}
+
+#[test]
+fn empty_and_missing_conversations() {
+    for json in [r#"{"conversations": []}"#, r#"{ "conversations" : [ ] , "x": 1 }"#, "{}",
+        r#"{"x": {"conversations": [1]}}"#]
+    {
+        assert!(ids(json.as_bytes()).is_empty(), "{}", json);
+        assert!(conversation_ranges(json.as_bytes()).unwrap().is_empty(), "{}", json);
+    }
+}
+
+#[test]
+fn truncated_input_is_an_error() {
+    let json = serde_json::to_vec(&json!({
+        "before": { "a": "x\"}", "b": [1, 2.5, null] },
+        "conversations": serde_json::from_str::<Value>(SAMPLE).unwrap()["conversations"],
+    })).unwrap();
+    // Every byte through the hand-walked part at the start, and a sample of those after it.
+    let start = json.windows(15).position(|w| w == b"\"conversations\"").unwrap() + 100;
+    let end = json.iter().rposition(|&b| b == b']').unwrap();
+    for len in (0..start).chain((start..end).step_by(37)) {
+        let truncated = &json[..len];
+        let results = Hangouts::stream_conversations(truncated).collect::<Vec<_>>();
+        assert!(results.last().is_some_and(Result::is_err), "truncated at {}", len);
+        assert!(results[..results.len() - 1].iter().all(Result::is_ok), "truncated at {}", len);
+        assert!(conversation_ranges(truncated).is_err(), "truncated at {}", len);
+    }
+}
+
+fn error_path(json: &Value) -> (String, Option<String>) {
+    let json = serde_json::to_vec(json).unwrap();
+    match Hangouts::from_reader(&json[..]) {
+        Err(Error::Json { path, conversation_id, .. }) => (path, conversation_id),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn error_paths() {
+    let sample: Value = serde_json::from_str(SAMPLE).unwrap();
+
+    let mut json = sample.clone();
+    json["conversations"][1]["conversation"]["conversation"]["participant_data"][0]["id"]
+        ["gaia_id"] = json!(5);
+    assert_eq!(error_path(&json), (
+        "conversations[1].conversation.conversation.participant_data[0].id.gaia_id".to_owned(),
+        Some("c2".to_owned())));
+
+    // Events are flattened, which serde can't follow paths through on its own.
+    let mut json = sample.clone();
+    json["conversations"][1]["events"][3]["timestamp"] = json!("yesterday");
+    assert_eq!(error_path(&json),
+        ("conversations[1].events[3].timestamp".to_owned(), Some("c2".to_owned())));
+
+    // Segments are internally tagged, so paths into them stop at the segment. With "lenient",
+    // this is kept as an unknown segment instead.
+    if !cfg!(feature = "lenient") {
+        let mut json = sample;
+        json["conversations"][0]["events"][0]["chat_message"]["message_content"]["segment"][0]
+            ["text"] = json!(["hi"]);
+        assert_eq!(error_path(&json), (
+            "conversations[0].events[0].chat_message.message_content.segment[0]".to_owned(),
+            Some("c1".to_owned())));
+    }
+}
@@ -0,0 +1,51 @@
+use std::borrow::Cow;
+
+use hangouts_json_parser::raw;
+use hangouts_json_parser::text::{AnnotationStyle, LinkStyle, Newlines, TextOptions};
+use serde_json::json;
+
+fn event(message: serde_json::Value) -> raw::Event {
+    serde_json::from_value(json!({
+        "conversation_id": { "id": "c1" },
+        "sender_id": { "gaia_id": "1", "chat_id": "1" },
+        "timestamp": "1500000000000000",
+        "self_event_state": { "user_id": { "gaia_id": "1", "chat_id": "1" },
+            "notification_level": "RING" },
+        "event_id": "e1",
+        "advances_sort_timestamp": true,
+        "event_otr": "ON_THE_RECORD",
+        "delivery_medium": { "medium_type": "BABEL_MEDIUM" },
+        "event_type": "REGULAR_CHAT_MESSAGE",
+        "event_version": "1",
+        "chat_message": message,
+    })).unwrap()
+}
+
+#[test]
+fn text_options() {
+    let single = event(json!({ "message_content": { "segment": [{ "type": "TEXT", "text": "hi" }] } }));
+    assert!(matches!(single.text(&TextOptions::new()), Some(Cow::Borrowed("hi"))));
+
+    let event = event(json!({
+        "message_content": {
+            "segment": [
+                { "type": "TEXT", "text": "look" },
+                { "type": "LINE_BREAK", "text": "\r\n" },
+                { "type": "LINK", "text": "here", "link_data": { "link_target": "https://a.example/" } },
+            ],
+            "attachment": [{ "id": "a1", "embed_item": { "type": ["PLACE_V2"], "id": "p" } }],
+        },
+        "annotation": [{ "type": 4, "value": "" }],
+    }));
+    assert_eq!(event.text(&TextOptions::new()).unwrap(), "look\r\nhere");
+    assert_eq!(event.text_only().unwrap(), "look\r\n");
+
+    let options = TextOptions::new()
+        .links(LinkStyle::Both)
+        .attachment_placeholder("[{type}]")
+        .annotations(AnnotationStyle::MeCommand)
+        .newlines(Newlines::Spaces);
+    assert_eq!(event.text(&options).unwrap(), "/me look here (https://a.example/) [place_v2]");
+    let options = TextOptions::new().links(LinkStyle::Url).newlines(Newlines::Normalize);
+    assert_eq!(event.text(&options).unwrap(), "look\nhttps://a.example/");
+}
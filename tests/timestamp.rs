@@ -0,0 +1,96 @@
+use hangouts_json_parser::{Error, Timestamp};
+
+#[test]
+fn from_str() {
+    let ts: Timestamp = "1500000000123456".parse().unwrap();
+    assert_eq!(ts.as_micros(), 1_500_000_000_123_456);
+    assert_eq!("-1".parse::<Timestamp>().unwrap(), Timestamp::from_micros(-1));
+
+    for bad in ["", "abc", "1.5", " 1", "9223372036854775808"] {
+        match bad.parse::<Timestamp>() {
+            Err(Error::Timestamp { value }) => assert_eq!(value, bad),
+            other => panic!("unexpected result for {:?}: {:?}", bad, other),
+        }
+    }
+}
+
+#[test]
+fn display() {
+    let cases = [
+        (0, "1970-01-01T00:00:00.000000Z"),
+        (1_500_000_000_123_456, "2017-07-14T02:40:00.123456Z"),
+        (951_782_400_000_000, "2000-02-29T00:00:00.000000Z"),
+        (-1, "1969-12-31T23:59:59.999999Z"),
+        (-86_400_000_001, "1969-12-30T23:59:59.999999Z"),
+        (-62_135_596_800_000_000, "0001-01-01T00:00:00.000000Z"),
+    ];
+    for &(micros, expected) in &cases {
+        assert_eq!(Timestamp::from_micros(micros).to_string(), expected);
+    }
+    assert!(Timestamp::from_micros(i64::MIN).to_string().starts_with("-290308-"));
+    assert!(Timestamp::from_micros(i64::MAX).to_string().starts_with("294247-"));
+}
+
+#[test]
+fn civil_round_trip() {
+    for days in (-719_000i64..3_000_000).step_by(997) {
+        let micros = days * 86_400_000_000 + 45_296_000_000;
+        let s = Timestamp::from_micros(micros).to_string();
+        let mut date = s[..s.find('T').unwrap()].split('-').map(|n| n.parse::<u32>().unwrap());
+        let (year, month, day) = (date.next().unwrap(), date.next().unwrap(), date.next().unwrap());
+        assert_eq!(&s[s.find('T').unwrap()..], "T12:34:56.000000Z");
+        assert_eq!(Timestamp::from_utc(i64::from(year), month, day, 45_296),
+            Some(Timestamp::from_micros(micros)), "{}", s);
+    }
+}
+
+#[test]
+fn from_utc_validation() {
+    assert!(Timestamp::from_utc(2016, 2, 29, 0).is_some());
+    assert!(Timestamp::from_utc(2000, 2, 29, 0).is_some());
+    assert!(Timestamp::from_utc(2017, 2, 29, 0).is_none());
+    assert!(Timestamp::from_utc(1900, 2, 29, 0).is_none());
+    assert!(Timestamp::from_utc(2017, 4, 31, 0).is_none());
+    assert!(Timestamp::from_utc(2017, 13, 1, 0).is_none());
+    assert!(Timestamp::from_utc(2017, 0, 1, 0).is_none());
+    assert!(Timestamp::from_utc(2017, 7, 0, 0).is_none());
+
+    // A leap second is the start of the next day.
+    assert_eq!(Timestamp::from_utc(2016, 12, 31, 86_400), Timestamp::from_utc(2017, 1, 1, 0));
+    assert!(Timestamp::from_utc(2016, 12, 31, 86_401).is_none());
+
+    for year in [300_000_000, -300_000_000, i64::MAX, i64::MIN] {
+        assert!(Timestamp::from_utc(year, 1, 1, 0).is_none(), "{}", year);
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono() {
+    use std::convert::TryFrom;
+    type DateTime = chrono::DateTime<chrono::Utc>;
+
+    let ts = Timestamp::from_micros(-1_500_000_000_123_456);
+    let dt = DateTime::try_from(ts).unwrap();
+    assert_eq!(dt.to_rfc3339_opts(chrono::SecondsFormat::Micros, true), ts.to_string());
+    assert_eq!(Timestamp::from(dt), ts);
+
+    match DateTime::try_from(Timestamp::from_micros(i64::MAX)) {
+        Err(Error::Timestamp { value }) => assert_eq!(value, i64::MAX.to_string()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time() {
+    use std::convert::TryFrom;
+
+    let ts = Timestamp::from_micros(1_500_000_000_123_456);
+    let dt = time::OffsetDateTime::try_from(ts).unwrap();
+    assert_eq!(dt.unix_timestamp_nanos(), 1_500_000_000_123_456_000);
+    assert_eq!(Timestamp::from(dt), ts);
+
+    assert!(matches!(time::OffsetDateTime::try_from(Timestamp::from_micros(i64::MIN)),
+        Err(Error::Timestamp { .. })));
+}